@@ -57,3 +57,13 @@ fn bench_highest_salary_scrapping_boilerplate(b: &mut test::Bencher) {
         test::black_box(highest_salary.query(&company));
     });
 }
+
+#[bench]
+fn bench_highest_salary_scrapping_boilerplate_by_ref(b: &mut test::Bencher) {
+    let company = Company::default();
+    let query = QueryRef::new(|e: &Employee| Some(&e.1));
+    let mut highest_salary = EverythingRef::new(query, cmp::max);
+    b.iter(|| {
+        test::black_box(highest_salary.query(&company));
+    });
+}
@@ -6,6 +6,11 @@ case "$JOB" in
     "test")
         cargo build $PROFILE --verbose --features "$FEATURES"
         cargo test  $PROFILE --verbose --features "$FEATURES"
+        cargo test  $PROFILE --verbose --features "derive $FEATURES" --test derive_feature
+        cargo build $PROFILE --verbose --no-default-features --features "$FEATURES"
+        cargo test  $PROFILE --verbose --no-default-features --features "$FEATURES"
+        cargo build $PROFILE --verbose --no-default-features --features "alloc $FEATURES"
+        cargo test  $PROFILE --verbose --no-default-features --features "alloc $FEATURES"
         ;;
     "bench")
         if [[ "$PROFILE" != "--release" ]]; then
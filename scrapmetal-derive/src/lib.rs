@@ -1,474 +1,1065 @@
+//! Custom derive support for `scrapmetal`.
+//!
+//! `#[derive(Term)]` implements `scrapmetal::Term` for a `struct` or `enum`,
+//! traversing each of its fields in order.
+//!
+//! The implementation requires each type parameter that appears in a traversed
+//! field to be a `Term`. Use `#[term(bound = "T: Term + Clone")]` on the type
+//! to give the where clause predicates yourself instead. Types with lifetime
+//! parameters are supported, though on stable Rust their implementation only
+//! applies when those lifetimes are `'static`.
+//!
+//! The implementation's `Term::clone_term` clones the value whenever its where
+//! clause is enough for the type to be `Clone`, so that it can be
+//! cloned-on-write behind a shared `Rc` or `Arc`.
+//!
+//! The generated code refers to `scrapmetal` as `::scrapmetal`. If it is
+//! renamed, or re-exported through another crate, give its path with
+//! `#[term(crate = "path")]` on the type.
+//!
+//! Fields accept these attributes:
+//!
+//! * `#[term(skip)]`: the field is not traversed at all. Transformations leave
+//!   it as is, and queries and mutations do not see it. Its type need not
+//!   implement `Term`.
+//!
+//! * `#[term(opaque)]`: the field is visited as though it were wrapped in a
+//!   `scrapmetal::Leaf`, and is not traversed into. Its type need not implement
+//!   `Term`.
+//!
+//! * `#[term(with = "module")]`: the field's children are visited by the
+//!   functions `map_one_transform`, `map_one_query`, `map_one_query_ref`,
+//!   `map_one_mutation`, `map_one_mutation_ref`, and `map_one_consume` in
+//!   `module`. Each takes the field, by value or by reference as in the
+//!   matching `Term` method, followed by the rest of that method's arguments.
+//!   Its type need not implement `Term`.
+//!
+//! * `#[term(mirror = "Mirror")]`: the field holds values of the remote type
+//!   that `Mirror` mirrors (see below), either directly or in a `Box`,
+//!   `Option`, `Vec`, or other `scrapmetal::RemoteField`. Each of them is
+//!   visited as a `scrapmetal::Remote<_, Mirror>`.
+//!
+//! For types defined in other crates, which cannot implement `Term` here,
+//! declare a mirror of the type with the same fields and variants, and derive
+//! `Term` for it with `#[term(remote = "other::Type")]`. Rather than an
+//! implementation of `Term`, this implements `scrapmetal::RemoteTerm` for the
+//! mirror, which makes `scrapmetal::Remote<other::Type, Mirror>` a `Term`, and
+//! generates the `map_one_*` functions above as associated functions of the
+//! mirror. Fields of the remote type can then be visited as `Remote` values
+//! with `#[term(mirror = "Mirror")]`, including the mirror's own fields when
+//! the remote type is recursive, or only have their children traversed with
+//! `#[term(with = "Mirror")]`. The remote type's fields must be public.
+
 #![recursion_limit = "1000"]
 
 extern crate proc_macro;
-extern crate syn;
+extern crate proc_macro2;
 #[macro_use]
 extern crate quote;
+#[macro_use]
+extern crate syn;
 
-use proc_macro::TokenStream;
-use quote::Tokens;
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 
-#[proc_macro_derive(Term)]
-pub fn derive_into_heap(input: TokenStream) -> TokenStream {
-    let source = input.to_string();
-    let ast = syn::parse_derive_input(&source).unwrap();
-    let expanded = impl_term(&ast);
+#[proc_macro_derive(Term, attributes(term))]
+pub fn derive_into_heap(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    let expanded = impl_term(&ast).unwrap_or_else(compile_errors);
 
     // Uncomment to debug the generated code...
-    // println!("\n\n{:?}", expanded);
+    // println!("\n\n{}", expanded);
+
+    expanded.into()
+}
+
+/// A `compile_error!` invocation for each of `error`'s messages, pointing at
+/// the offending tokens.
+///
+/// Unlike `syn::Error::into_compile_error`, this does not refer to the macro
+/// by its `::core` path, which does not resolve in Rust 2015 crates.
+fn compile_errors(error: syn::Error) -> TokenStream {
+    let errors = error.into_iter().map(|error| {
+        let message = error.to_string();
+        quote_spanned! { error.span()=>
+            compile_error!(#message);
+        }
+    });
+    quote! { #( #errors )* }
+}
 
-    expanded.parse().unwrap()
+/// Give every token in `tokens` the given span.
+fn respan(tokens: TokenStream, span: proc_macro2::Span) -> TokenStream {
+    tokens.into_iter()
+        .map(|mut token| {
+            if let proc_macro2::TokenTree::Group(ref mut group) = token {
+                let mut respanned = proc_macro2::Group::new(
+                    group.delimiter(),
+                    respan(group.stream(), span),
+                );
+                respanned.set_span(span);
+                *group = respanned;
+            }
+            token.set_span(span);
+            token
+        })
+        .collect()
 }
 
-fn impl_term(ast: &syn::DeriveInput) -> Tokens {
-    match ast.body {
-        syn::Body::Struct(ref data) => impl_term_for_struct(ast, data),
-        syn::Body::Enum(ref variants) => impl_term_for_enum(ast, variants),
+fn impl_term(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    if let syn::Data::Union(ref data) = ast.data {
+        return Err(syn::Error::new_spanned(
+            data.union_token,
+            "`Term` cannot be derived for unions, because which field to traverse is unknown",
+        ));
+    }
+
+    let container = Container::new(ast)?;
+
+    let mut generics = ast.generics.clone();
+    let bounds = bounds(ast, &container)?;
+    generics.make_where_clause().predicates.extend(bounds);
+
+    let krate = &container.krate;
+    let (path, this) = match container.remote {
+        Some(ref remote) => (quote! { #remote }, quote! { this }),
+        None => {
+            let name = &ast.ident;
+            (quote! { #name }, quote! { self })
+        }
+    };
+
+    let bodies = match ast.data {
+        syn::Data::Struct(ref data) => match data.fields {
+            syn::Fields::Named(_) => struct_bodies(krate, &path, &this, &data.fields)?,
+            syn::Fields::Unnamed(_) => tuple_struct_bodies(krate, &path, &this, &data.fields)?,
+            syn::Fields::Unit => unit_struct_bodies(&this),
+        },
+        syn::Data::Enum(ref data) => enum_bodies(krate, &path, &this, &data.variants)?,
+        syn::Data::Union(_) => unreachable!(),
+    };
+
+    let bodies = bodies.map(|body| quote! {
+        #[allow(unused_imports)]
+        use #krate::export::{NotTermField, TermField, TermFieldMut, TermFieldRef};
+        #body
+    });
+
+    let assertions = field_assertions(ast, &generics, krate)?;
+    let methods = match container.remote {
+        Some(ref remote) => impl_remote_functions(ast, &generics, krate, remote, &bodies),
+        None => impl_term_methods(ast, &generics, krate, &bodies),
+    };
+
+    Ok(quote! {
+        #assertions
+        #methods
+    })
+}
+
+/// Assert that the type of each field whose children are traversed is a
+/// `Term`, so that a field missing an implementation is reported once, at the
+/// field's type, rather than by each of the generated methods.
+fn field_assertions(
+    ast: &syn::DeriveInput,
+    generics: &syn::Generics,
+    krate: &syn::Path,
+) -> syn::Result<TokenStream> {
+    let fields: Vec<_> = match ast.data {
+        syn::Data::Struct(ref data) => data.fields.iter().collect(),
+        syn::Data::Enum(ref data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        syn::Data::Union(_) => vec![],
+    };
+    let mut assertions = vec![];
+    for field in fields {
+        if let Traversal::Children = traversal(field)? {
+            let ty = &field.ty;
+            assertions.push(quote_spanned! { ty.span()=>
+                #krate::export::assert_field_term::<#ty>();
+            });
+        }
+    }
+    if assertions.is_empty() {
+        return Ok(quote! {});
+    }
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        const _: () = {
+            impl #impl_generics #name #ty_generics
+                #where_clause
+            {
+                #[allow(dead_code)]
+                fn __term_assert_fields() {
+                    #( #assertions )*
+                }
+            }
+        };
+    })
+}
+
+/// The where clause predicates that the `Term` implementation needs, beyond
+/// those on the type definition itself.
+///
+/// Every type parameter that appears in a traversed field must be a `Term`,
+/// unless the type has a `#[term(bound = "...")]` attribute, in which case
+/// that is used instead. And if the type is generic at all, it must itself be
+/// `Castable`, which on stable Rust requires its lifetime parameters to be
+/// `'static`.
+fn bounds(ast: &syn::DeriveInput, container: &Container) -> syn::Result<Vec<syn::WherePredicate>> {
+    let generics = &ast.generics;
+    if generics.params.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let krate = &container.krate;
+    let mut bounds = vec![];
+    if container.remote.is_none() {
+        bounds.push(parse_quote! { Self: #krate::Castable });
+    }
+
+    if let Some(ref bound) = container.bound {
+        bounds.extend(bound.iter().cloned());
+        return Ok(bounds);
     }
+
+    let fields: Vec<_> = match ast.data {
+        syn::Data::Struct(ref data) => data.fields.iter().collect(),
+        syn::Data::Enum(ref data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        syn::Data::Union(_) => vec![],
+    };
+    let mut traversed = vec![];
+    for field in fields {
+        if let Traversal::Children = traversal(field)? {
+            traversed.push(&field.ty);
+        }
+    }
+
+    for param in generics.type_params() {
+        let ident = &param.ident;
+        if traversed.iter().any(|ty| mentions(ty.to_token_stream(), ident)) {
+            bounds.push(parse_quote! { #ident: #krate::Term });
+        }
+    }
+
+    Ok(bounds)
+}
+
+/// Whether `tokens` mention `ident`, including within any delimited groups.
+fn mentions(tokens: TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ref i) => i == ident,
+        proc_macro2::TokenTree::Group(ref group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// The bodies of the `Term` methods for a type, which refer to the value being
+/// traversed as `this`.
+struct Bodies {
+    transform: TokenStream,
+    query: TokenStream,
+    query_ref: TokenStream,
+    mutation: TokenStream,
+    mutation_ref: TokenStream,
+    consume: TokenStream,
 }
 
-fn impl_term_for_struct(ast: &syn::DeriveInput, data: &syn::VariantData) -> Tokens {
-    match *data {
-        syn::VariantData::Struct(ref fields) => impl_term_for_struct_struct(ast, fields),
-        syn::VariantData::Tuple(ref fields) => impl_term_for_tuple_struct(ast, fields),
-        syn::VariantData::Unit => impl_term_for_unit_struct(ast),
+impl Bodies {
+    /// Apply `f` to each of the bodies.
+    fn map<F>(self, mut f: F) -> Bodies
+    where
+        F: FnMut(TokenStream) -> TokenStream,
+    {
+        Bodies {
+            transform: f(self.transform),
+            query: f(self.query),
+            query_ref: f(self.query_ref),
+            mutation: f(self.mutation),
+            mutation_ref: f(self.mutation_ref),
+            consume: f(self.consume),
+        }
+    }
+
+    /// Build each of the bodies that visit the children with `visit`.
+    fn new<F>(transform: TokenStream, mut visit: F) -> Bodies
+    where
+        F: FnMut(Visit) -> TokenStream,
+    {
+        Bodies {
+            transform,
+            query: visit(Visit::Query),
+            query_ref: visit(Visit::QueryRef),
+            mutation: visit(Visit::Mutation),
+            mutation_ref: visit(Visit::MutationRef),
+            consume: visit(Visit::Consume),
+        }
     }
 }
 
-fn impl_term_for_struct_struct(ast: &syn::DeriveInput, fields: &[syn::Field]) -> Tokens {
+fn impl_term_methods(
+    ast: &syn::DeriveInput,
+    generics: &syn::Generics,
+    krate: &syn::Path,
+    bodies: &Bodies,
+) -> TokenStream {
     let name = &ast.ident;
-    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let Bodies {
+        ref transform,
+        ref query,
+        ref query_ref,
+        ref mutation,
+        ref mutation_ref,
+        ref consume,
+    } = *bodies;
 
-    let transforms: Vec<_> = fields.iter()
-        .map(|f| {
-            quote! {
-                #f.ident : f.transform(self.#f.ident) ,
+    quote! {
+        impl #impl_generics #krate::Term for #name #ty_generics
+            #where_clause
+        {
+            #[inline]
+            #[allow(unused_variables)]
+            #[allow(unused_mut)]
+            fn map_one_transform<__TermF>(self, f: &mut __TermF) -> Self
+            where
+                __TermF: #krate::GenericTransform,
+            {
+                #transform
             }
-        })
-        .collect();
 
-    let queries: Vec<_> = fields.iter()
-        .map(|f| {
-            quote! {
-                let r = q.query(&self.#f.ident);
-                each(q, r);
+            #[inline]
+            #[allow(unused_variables)]
+            #[allow(unused_mut)]
+            fn map_one_query<__TermQ, __TermR, __TermF>(&self, q: &mut __TermQ, mut each: __TermF)
+            where
+                __TermQ: #krate::GenericQuery<__TermR>,
+                __TermF: FnMut(&mut __TermQ, __TermR),
+            {
+                #query
             }
-        })
-        .collect();
 
-    let mutations: Vec<_> = fields.iter()
-        .map(|f| {
-            quote! {
-                let r = m.mutate(&mut self.#f.indent);
-                each(m, r);
+            #[inline]
+            #[allow(unused_variables)]
+            #[allow(unused_mut)]
+            fn map_one_query_ref<'__term, __TermQ, __TermR, __TermF>(
+                &'__term self,
+                q: &mut __TermQ,
+                mut each: __TermF,
+            )
+            where
+                __TermQ: #krate::GenericQueryRef<'__term, __TermR>,
+                __TermF: FnMut(&mut __TermQ, __TermR),
+            {
+                #query_ref
             }
-        })
-        .collect();
 
-    quote! {
-        impl #impl_generics ::scrapmetal::Term for #name #ty_generics
-            #where_clause
-        {
             #[inline]
             #[allow(unused_variables)]
             #[allow(unused_mut)]
-            fn map_one_transform<F>(self, f: &mut F) -> Self
+            fn map_one_mutation<__TermM, __TermR, __TermF>(
+                &mut self,
+                m: &mut __TermM,
+                mut each: __TermF,
+            )
             where
-                F: ::scrapmetal::GenericTransform,
+                __TermM: #krate::GenericMutate<__TermR>,
+                __TermF: FnMut(&mut __TermM, __TermR),
             {
-                Self {
-                    #( #transforms )*
-                }
+                #mutation
             }
 
             #[inline]
             #[allow(unused_variables)]
             #[allow(unused_mut)]
-            fn map_one_query<Q, R, F>(&self, q: &mut Q, mut each: F)
+            fn map_one_mutation_ref<'__term, __TermM, __TermR, __TermF>(
+                &'__term mut self,
+                m: &mut __TermM,
+                mut each: __TermF,
+            )
             where
-                Q: ::scrapmetal::GenericQuery<R>,
-                F: FnMut(&mut Q, R),
+                __TermM: #krate::GenericMutateRef<'__term, __TermR>,
+                __TermF: FnMut(&mut __TermM, __TermR),
             {
-                #( #queries )*
+                #mutation_ref
             }
 
             #[inline]
             #[allow(unused_variables)]
             #[allow(unused_mut)]
-            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            fn map_one_consume<__TermC, __TermR, __TermF>(self, c: &mut __TermC, mut each: __TermF)
             where
-                M: ::scrapmetal::GenericMutate<R>,
-                F: FnMut(&mut M, R),
+                __TermC: #krate::GenericConsume<__TermR>,
+                __TermF: FnMut(&mut __TermC, __TermR),
             {
-                #( #mutations )*
+                #consume
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<Self> {
+                #[allow(unused_imports)]
+                use #krate::export::{NotTermClone, TermClone};
+                #krate::export::CloneProbe(self).clone_term()
             }
         }
     }
 }
 
-fn impl_term_for_tuple_struct(ast: &syn::DeriveInput, fields: &[syn::Field]) -> Tokens {
+/// Implement the `Term` methods for the remote type as associated functions of
+/// the local mirror type, for use with `#[term(with = "Mirror")]`, and
+/// implement `RemoteTerm` for the mirror with them.
+fn impl_remote_functions(
+    ast: &syn::DeriveInput,
+    generics: &syn::Generics,
+    krate: &syn::Path,
+    remote: &syn::Path,
+    bodies: &Bodies,
+) -> TokenStream {
     let name = &ast.ident;
-    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let remote = quote! { #remote #ty_generics };
+    let mirror = quote! { <#name #ty_generics> };
+    let Bodies {
+        ref transform,
+        ref query,
+        ref query_ref,
+        ref mutation,
+        ref mutation_ref,
+        ref consume,
+    } = *bodies;
 
-    let fields: Vec<_> = (0..fields.len()).map(syn::Ident::new).collect();
-
-    let transforms: Vec<_> = fields.iter()
-        .map(|i| {
-            quote! {
-                f.transform(self.#i) ,
+    quote! {
+        #[allow(missing_docs)]
+        impl #impl_generics #name #ty_generics
+            #where_clause
+        {
+            #[inline]
+            #[allow(unused_variables)]
+            #[allow(unused_mut)]
+            pub fn map_one_transform<__TermF>(this: #remote, f: &mut __TermF) -> #remote
+            where
+                __TermF: #krate::GenericTransform,
+            {
+                #transform
             }
-        })
-        .collect();
 
-    let queries: Vec<_> = fields.iter()
-        .map(|i| {
-            quote! {
-                let r = q.query(&self.#i);
-                each(q, r);
+            #[inline]
+            #[allow(unused_variables)]
+            #[allow(unused_mut)]
+            pub fn map_one_query<__TermQ, __TermR, __TermF>(
+                this: &#remote,
+                q: &mut __TermQ,
+                mut each: __TermF,
+            )
+            where
+                __TermQ: #krate::GenericQuery<__TermR>,
+                __TermF: FnMut(&mut __TermQ, __TermR),
+            {
+                #query
             }
-        })
-        .collect();
 
-    let mutations: Vec<_> = fields.iter()
-        .map(|i| {
-            quote! {
-                let r = m.mutate(&mut self.#i);
-                each(m, r);
+            #[inline]
+            #[allow(unused_variables)]
+            #[allow(unused_mut)]
+            pub fn map_one_query_ref<'__term, __TermQ, __TermR, __TermF>(
+                this: &'__term #remote,
+                q: &mut __TermQ,
+                mut each: __TermF,
+            )
+            where
+                __TermQ: #krate::GenericQueryRef<'__term, __TermR>,
+                __TermF: FnMut(&mut __TermQ, __TermR),
+            {
+                #query_ref
             }
-        })
-        .collect();
 
-    quote! {
-        impl #impl_generics ::scrapmetal::Term for #name #ty_generics
-            #where_clause
-        {
             #[inline]
             #[allow(unused_variables)]
             #[allow(unused_mut)]
-            fn map_one_transform<F>(self, f: &mut F) -> Self
+            pub fn map_one_mutation<__TermM, __TermR, __TermF>(
+                this: &mut #remote,
+                m: &mut __TermM,
+                mut each: __TermF,
+            )
             where
-                F: ::scrapmetal::GenericTransform,
+                __TermM: #krate::GenericMutate<__TermR>,
+                __TermF: FnMut(&mut __TermM, __TermR),
             {
-                #name ( #( #transforms )* )
+                #mutation
             }
 
             #[inline]
             #[allow(unused_variables)]
             #[allow(unused_mut)]
-            fn map_one_query<Q, R, F>(&self, q: &mut Q, mut each: F)
+            pub fn map_one_mutation_ref<'__term, __TermM, __TermR, __TermF>(
+                this: &'__term mut #remote,
+                m: &mut __TermM,
+                mut each: __TermF,
+            )
             where
-                Q: ::scrapmetal::GenericQuery<R>,
-                F: FnMut(&mut Q, R),
+                __TermM: #krate::GenericMutateRef<'__term, __TermR>,
+                __TermF: FnMut(&mut __TermM, __TermR),
             {
-                #( #queries )*
+                #mutation_ref
             }
 
             #[inline]
             #[allow(unused_variables)]
             #[allow(unused_mut)]
-            fn map_one_mutation<M, R, F>(&mut self, m: &mut M, mut each: F)
+            pub fn map_one_consume<__TermC, __TermR, __TermF>(
+                this: #remote,
+                c: &mut __TermC,
+                mut each: __TermF,
+            )
             where
-                M: ::scrapmetal::GenericMutate<R>,
-                F: FnMut(&mut M, R),
+                __TermC: #krate::GenericConsume<__TermR>,
+                __TermF: FnMut(&mut __TermC, __TermR),
             {
-                #( #mutations )*
+                #consume
             }
         }
-    }
-}
-
-fn impl_term_for_unit_struct(ast: &syn::DeriveInput) -> Tokens {
-    let name = &ast.ident;
-    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    quote! {
-        impl #impl_generics ::scrapmetal::Term for #name #ty_generics
+        impl #impl_generics #krate::RemoteTerm for #name #ty_generics
             #where_clause
         {
-            #[inline(always)]
-            fn map_one_transform<F>(self, _: &mut F) -> Self
+            type Remote = #remote;
+
+            #[inline]
+            fn map_one_transform<__TermF>(this: #remote, f: &mut __TermF) -> #remote
             where
-                F: ::scrapmetal::GenericTransform,
+                __TermF: #krate::GenericTransform,
             {
-                self
+                #mirror::map_one_transform(this, f)
             }
 
-            #[inline(always)]
-            fn map_one_query<Q, R, F>(&self, _: &mut Q, _: F)
+            #[inline]
+            fn map_one_query<__TermQ, __TermR, __TermF>(
+                this: &#remote,
+                q: &mut __TermQ,
+                each: __TermF,
+            )
             where
-                Q: ::scrapmetal::GenericQuery<R>,
-                F: FnMut(&mut Q, R),
-            {}
+                __TermQ: #krate::GenericQuery<__TermR>,
+                __TermF: FnMut(&mut __TermQ, __TermR),
+            {
+                #mirror::map_one_query(this, q, each)
+            }
 
-            #[inline(always)]
-            fn map_one_mutation<M, R, F>(&mut self, _: &mut M, _: F)
+            #[inline]
+            fn map_one_query_ref<'__term, __TermQ, __TermR, __TermF>(
+                this: &'__term #remote,
+                q: &mut __TermQ,
+                each: __TermF,
+            )
+            where
+                __TermQ: #krate::GenericQueryRef<'__term, __TermR>,
+                __TermF: FnMut(&mut __TermQ, __TermR),
+            {
+                #mirror::map_one_query_ref(this, q, each)
+            }
+
+            #[inline]
+            fn map_one_mutation<__TermM, __TermR, __TermF>(
+                this: &mut #remote,
+                m: &mut __TermM,
+                each: __TermF,
+            )
+            where
+                __TermM: #krate::GenericMutate<__TermR>,
+                __TermF: FnMut(&mut __TermM, __TermR),
+            {
+                #mirror::map_one_mutation(this, m, each)
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'__term, __TermM, __TermR, __TermF>(
+                this: &'__term mut #remote,
+                m: &mut __TermM,
+                each: __TermF,
+            )
             where
-                M: ::scrapmetal::GenericMutate<R>,
-                F: FnMut(&mut M, R),
-            {}
+                __TermM: #krate::GenericMutateRef<'__term, __TermR>,
+                __TermF: FnMut(&mut __TermM, __TermR),
+            {
+                #mirror::map_one_mutation_ref(this, m, each)
+            }
+
+            #[inline]
+            fn map_one_consume<__TermC, __TermR, __TermF>(
+                this: #remote,
+                c: &mut __TermC,
+                each: __TermF,
+            )
+            where
+                __TermC: #krate::GenericConsume<__TermR>,
+                __TermF: FnMut(&mut __TermC, __TermR),
+            {
+                #mirror::map_one_consume(this, c, each)
+            }
         }
     }
 }
 
-fn impl_term_for_enum(ast: &syn::DeriveInput, variants: &[syn::Variant]) -> Tokens {
-    let name = &ast.ident;
-    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
-
-    let transforms: Vec<_> = variants.iter()
-        .map(|v| {
-            let variant_ident = &v.ident;
-            match v.data {
-                syn::VariantData::Struct(ref fields) => {
-                    let field_names: Vec<_> = fields.iter()
-                        .map(|f| {
-                            let ident = &f.ident;
-                            quote! {
-                                #ident ,
-                            }
-                        })
-                        .collect();
-
-                    let field_transforms: Vec<_> = fields.iter()
-                        .map(|f| {
-                            let ident = &f.ident;
-                            quote! {
-                                #ident : f.transform( #ident ) ,
-                            }
-                        })
-                        .collect();
+fn struct_bodies(
+    krate: &syn::Path,
+    path: &TokenStream,
+    this: &TokenStream,
+    fields: &syn::Fields,
+) -> syn::Result<Bodies> {
+    let fields = traversed_fields(krate, fields)?;
 
-                    quote! {
-                        #name :: #variant_ident { #( #field_names )* } => {
-                            #name :: #variant_ident { #( #field_transforms )* }
-                        }
-                    }
-                }
-                syn::VariantData::Tuple(ref fields) => {
-                    let tuple_names: Vec<_> = (0..fields.len())
-                        .map(|i| {
-                            let c = ('a' as u8 + i as u8) as char;
-                            let mut s = String::with_capacity(1);
-                            s.push(c);
-                            syn::Ident::new(s)
-                        })
-                        .collect();
+    let transforms: Vec<_> = fields.iter()
+        .map(|field| {
+            let member = &field.member;
+            let value = field.transform(quote! { #this.#member });
+            quote! {
+                #member : #value ,
+            }
+        })
+        .collect();
 
-                    let tuple_patterns: Vec<_> = tuple_names.iter()
-                        .map(|p| {
-                            quote! {
-                                #p ,
-                            }
-                        })
-                        .collect();
+    Ok(Bodies::new(
+        quote! {
+            #path { #( #transforms )* }
+        },
+        |visit| {
+            let visits: Vec<_> = fields.iter()
+                .map(|field| {
+                    let member = &field.member;
+                    let value = match visit {
+                        Visit::Query | Visit::QueryRef => quote! { &#this.#member },
+                        Visit::Mutation | Visit::MutationRef => quote! { &mut #this.#member },
+                        Visit::Consume => quote! { #this.#member },
+                    };
+                    field.visit(visit, value)
+                })
+                .collect();
+            quote! { #( #visits )* }
+        },
+    ))
+}
 
-                    let tuple_transforms: Vec<_> = tuple_names.iter()
-                        .map(|p| {
-                            quote! {
-                                f.transform( #p ) ,
-                            }
-                        })
-                        .collect();
+fn tuple_struct_bodies(
+    krate: &syn::Path,
+    path: &TokenStream,
+    this: &TokenStream,
+    fields: &syn::Fields,
+) -> syn::Result<Bodies> {
+    let fields = traversed_fields(krate, fields)?;
 
-                    quote! {
-                        #name :: #variant_ident ( #( #tuple_patterns )* ) => {
-                            #name :: #variant_ident ( #( #tuple_transforms )* )
-                        }
-                    }
-                }
-                syn::VariantData::Unit => {
-                    quote! {
-                        // Nothing to do here.
-                    }
-                }
+    let transforms: Vec<_> = fields.iter()
+        .map(|field| {
+            let member = &field.member;
+            let value = field.transform(quote! { #this.#member });
+            quote! {
+                #value ,
             }
         })
         .collect();
 
-    let queries: Vec<_> = variants.iter()
-        .map(|v| {
-            let variant_ident = &v.ident;
-            match v.data {
-                syn::VariantData::Struct(ref fields) => {
-                    let field_names: Vec<_> = fields.iter()
-                        .map(|f| {
-                            let ident = &f.ident;
-                            quote! {
-                                ref #ident ,
-                            }
-                        })
-                        .collect();
+    Ok(Bodies::new(
+        quote! {
+            #path ( #( #transforms )* )
+        },
+        |visit| {
+            let visits: Vec<_> = fields.iter()
+                .map(|field| {
+                    let member = &field.member;
+                    let value = match visit {
+                        Visit::Query | Visit::QueryRef => quote! { &#this.#member },
+                        Visit::Mutation | Visit::MutationRef => quote! { &mut #this.#member },
+                        Visit::Consume => quote! { #this.#member },
+                    };
+                    field.visit(visit, value)
+                })
+                .collect();
+            quote! { #( #visits )* }
+        },
+    ))
+}
 
-                    let field_queries: Vec<_> = fields.iter()
-                        .map(|f| {
-                            let ident = &f.ident;
-                            quote! {
-                                let r = q.query( #ident );
-                                each(q, r);
-                            }
-                        })
-                        .collect();
+fn unit_struct_bodies(this: &TokenStream) -> Bodies {
+    Bodies::new(quote! { #this }, |_| quote! {})
+}
 
+fn enum_bodies(
+    krate: &syn::Path,
+    path: &TokenStream,
+    this: &TokenStream,
+    variants: &Punctuated<syn::Variant, Token![,]>,
+) -> syn::Result<Bodies> {
+    let mut matched = vec![];
+    for variant in variants {
+        if let Some(attr) = variant.attrs.iter().find(|attr| attr.path().is_ident("term")) {
+            return Err(syn::Error::new_spanned(
+                attr.path(),
+                "`term` attributes are not supported on variants; put them on fields instead",
+            ));
+        }
+        matched.push((variant, traversed_fields(krate, &variant.fields)?));
+    }
+
+    let transforms: Vec<_> = matched.iter()
+        .map(|&(variant, ref fields)| {
+            let pattern = variant_pattern(path, variant, fields, quote! {});
+            let variant_ident = &variant.ident;
+            let values: Vec<_> = fields.iter()
+                .map(|field| {
+                    let binding = field.binding();
+                    field.transform(quote! { #binding })
+                })
+                .collect();
+
+            let rebuilt = match variant.fields {
+                syn::Fields::Named(_) => {
+                    let members = fields.iter().map(|field| &field.member);
                     quote! {
-                        #name :: #variant_ident { #( #field_names )* } => {
-                            #( #field_queries )*
-                        }
+                        #path :: #variant_ident { #( #members : #values , )* }
                     }
                 }
-                syn::VariantData::Tuple(ref fields) => {
-                    let tuple_names: Vec<_> = (0..fields.len())
-                        .map(|i| {
-                            let c = ('a' as u8 + i as u8) as char;
-                            let mut s = String::with_capacity(1);
-                            s.push(c);
-                            syn::Ident::new(s)
-                        })
-                        .collect();
-
-                    let tuple_patterns: Vec<_> = tuple_names.iter()
-                        .map(|p| {
-                            quote! {
-                                ref #p ,
-                            }
-                        })
-                        .collect();
-
-                    let tuple_queries: Vec<_> = tuple_names.iter()
-                        .map(|p| {
-                            quote! {
-                                let r = q.query( #p );
-                                each(q, r);
-                            }
-                        })
-                        .collect();
-
+                syn::Fields::Unnamed(_) => {
                     quote! {
-                        #name :: #variant_ident ( #( #tuple_patterns )* ) => {
-                            #( #tuple_queries )*
-                        }
+                        #path :: #variant_ident ( #( #values , )* )
                     }
                 }
-                syn::VariantData::Unit => {
+                syn::Fields::Unit => {
                     quote! {
-                        // Nothing to do here.
+                        #path :: #variant_ident
                     }
                 }
+            };
+
+            quote! {
+                #pattern => #rebuilt ,
             }
         })
         .collect();
 
-    let mutations: Vec<_> = variants.iter()
-        .map(|v| {
-            let variant_ident = &v.ident;
-            match v.data {
-                syn::VariantData::Struct(ref fields) => {
-                    let field_names: Vec<_> = fields.iter()
-                        .map(|f| {
-                            let ident = &f.ident;
-                            quote! {
-                                ref mut #ident ,
-                            }
-                        })
-                        .collect();
-
-                    let field_mutations: Vec<_> = fields.iter()
-                        .map(|f| {
-                            let ident = &f.ident;
-                            quote! {
-                                let r = m.mutate( #ident );
-                                each(m, r);
-                            }
+    Ok(Bodies::new(
+        quote! {
+            match #this {
+                #( #transforms )*
+            }
+        },
+        |visit| {
+            let (scrutinee, mode) = match visit {
+                Visit::Query | Visit::QueryRef => (quote! { *#this }, quote! { ref }),
+                Visit::Mutation | Visit::MutationRef => (quote! { *#this }, quote! { ref mut }),
+                Visit::Consume => (quote! { #this }, quote! {}),
+            };
+            let arms: Vec<_> = matched.iter()
+                .map(|&(variant, ref fields)| {
+                    let pattern = variant_pattern(path, variant, fields, mode.clone());
+                    let visits: Vec<_> = fields.iter()
+                        .map(|field| {
+                            let binding = field.binding();
+                            field.visit(visit, quote! { #binding })
                         })
                         .collect();
 
                     quote! {
-                        #name :: #variant_ident { #( #field_names )* } => {
-                            #( #field_mutations )*
+                        #pattern => {
+                            #( #visits )*
                         }
                     }
+                })
+                .collect();
+
+            quote! {
+                match #scrutinee {
+                    #( #arms )*
                 }
-                syn::VariantData::Tuple(ref fields) => {
-                    let tuple_names: Vec<_> = (0..fields.len())
-                        .map(|i| {
-                            let c = ('a' as u8 + i as u8) as char;
-                            let mut s = String::with_capacity(1);
-                            s.push(c);
-                            syn::Ident::new(s)
-                        })
-                        .collect();
+            }
+        },
+    ))
+}
 
-                    let tuple_patterns: Vec<_> = tuple_names.iter()
-                        .map(|p| {
-                            quote! {
-                                ref mut #p ,
-                            }
-                        })
-                        .collect();
+/// A pattern matching the given variant, binding each of its fields with
+/// `mode`, which is `ref`, `ref mut`, or nothing.
+fn variant_pattern(
+    path: &TokenStream,
+    variant: &syn::Variant,
+    fields: &[TraversedField],
+    mode: TokenStream,
+) -> TokenStream {
+    let variant_ident = &variant.ident;
+    let bindings: Vec<_> = fields.iter().map(TraversedField::binding).collect();
 
-                    let tuple_mutations: Vec<_> = tuple_names.iter()
-                        .map(|p| {
-                            quote! {
-                                let r = m.mutate( #p );
-                                each(m, r);
-                            }
-                        })
-                        .collect();
+    match variant.fields {
+        syn::Fields::Named(_) => {
+            let members = fields.iter().map(|field| &field.member);
+            quote! {
+                #path :: #variant_ident { #( #members : #mode #bindings , )* }
+            }
+        }
+        syn::Fields::Unnamed(_) => quote! {
+            #path :: #variant_ident ( #( #mode #bindings , )* )
+        },
+        syn::Fields::Unit => quote! {
+            #path :: #variant_ident
+        },
+    }
+}
 
-                    quote! {
-                        #name :: #variant_ident ( #( #tuple_patterns )* ) => {
-                            #( #tuple_mutations )*
-                        }
-                    }
-                }
-                syn::VariantData::Unit => {
-                    quote! {
-                        // Nothing to do here.
-                    }
+/// The type's `#[term(...)]` attributes.
+struct Container {
+    /// `#[term(bound = "...")]`: the where clause predicates to use instead of
+    /// the inferred ones.
+    bound: Option<Punctuated<syn::WherePredicate, Token![,]>>,
+    /// `#[term(remote = "path")]`: the type in another crate that this type
+    /// mirrors.
+    remote: Option<syn::Path>,
+    /// `#[term(crate = "path")]`: the path to the `scrapmetal` crate, which is
+    /// `::scrapmetal` by default.
+    krate: syn::Path,
+}
+
+impl Container {
+    fn new(ast: &syn::DeriveInput) -> syn::Result<Container> {
+        let mut container = Container {
+            bound: None,
+            remote: None,
+            krate: parse_quote! { ::scrapmetal },
+        };
+
+        for attr in ast.attrs.iter().filter(|attr| attr.path().is_ident("term")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bound") {
+                    let bound: syn::LitStr = meta.value()?.parse()?;
+                    container.bound = Some(bound.parse_with(Punctuated::parse_terminated)?);
+                    Ok(())
+                } else if meta.path.is_ident("remote") {
+                    let remote: syn::LitStr = meta.value()?.parse()?;
+                    container.remote = Some(remote.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("crate") {
+                    let krate: syn::LitStr = meta.value()?.parse()?;
+                    container.krate = krate.parse()?;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown `term` container attribute"))
                 }
+            })?;
+        }
+
+        Ok(container)
+    }
+}
+
+/// How a field is traversed, as configured by its `#[term(...)]` attributes.
+enum Traversal {
+    /// The default: the field is visited, and traversed into.
+    Children,
+    /// `#[term(skip)]`: the field is neither visited nor traversed into.
+    Skip,
+    /// `#[term(opaque)]`: the field is visited as a `Leaf`.
+    Opaque,
+    /// `#[term(with = "path")]`: the field's children are visited by the
+    /// `map_one_*` functions in the given module.
+    With(syn::Path),
+    /// `#[term(mirror = "Type")]`: each remote value in the field is visited as
+    /// a `Remote` with the given mirror.
+    Mirror(syn::Type),
+}
+
+/// Which of the visiting `Term` methods is being generated.
+#[derive(Clone, Copy)]
+enum Visit {
+    Query,
+    QueryRef,
+    Mutation,
+    MutationRef,
+    Consume,
+}
+
+fn traversal(field: &syn::Field) -> syn::Result<Traversal> {
+    let mut traversal = Traversal::Children;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("term")) {
+        attr.parse_nested_meta(|meta| {
+            let parsed = if meta.path.is_ident("skip") {
+                Traversal::Skip
+            } else if meta.path.is_ident("opaque") {
+                Traversal::Opaque
+            } else if meta.path.is_ident("with") {
+                let path: syn::LitStr = meta.value()?.parse()?;
+                Traversal::With(path.parse()?)
+            } else if meta.path.is_ident("mirror") {
+                let mirror: syn::LitStr = meta.value()?.parse()?;
+                Traversal::Mirror(mirror.parse()?)
+            } else {
+                return Err(meta.error("unknown `term` field attribute"));
+            };
+
+            if let Traversal::Children = traversal {
+                traversal = parsed;
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "a field may only have one of `skip`, `opaque`, `with`, and `mirror`",
+                ))
             }
+        })?;
+    }
+
+    Ok(traversal)
+}
+
+/// A field of a struct or variant, and how it is traversed.
+struct TraversedField<'a> {
+    krate: &'a syn::Path,
+    member: syn::Member,
+    traversal: Traversal,
+    ty: &'a syn::Type,
+}
+
+fn traversed_fields<'a>(
+    krate: &'a syn::Path,
+    fields: &'a syn::Fields,
+) -> syn::Result<Vec<TraversedField<'a>>> {
+    fields.iter()
+        .enumerate()
+        .map(|(i, field)| {
+            Ok(TraversedField {
+                krate,
+                member: match field.ident {
+                    Some(ref ident) => syn::Member::Named(ident.clone()),
+                    None => syn::Member::Unnamed(i.into()),
+                },
+                traversal: traversal(field)?,
+                ty: &field.ty,
+            })
         })
-        .collect();
+        .collect()
+}
 
-    quote! {
-        impl #impl_generics ::scrapmetal::Term for #name #ty_generics
-            #where_clause
-        {
-            #[inline]
-            #[allow(unused_variables)]
-            #[allow(unused_mut)]
-            fn map_one_transform<F>(self, f: &mut F) -> Self
-            where
-                F: ::scrapmetal::GenericTransform,
-            {
-                match self {
-                    #( #transforms )*
-                }
-            }
+impl<'a> TraversedField<'a> {
+    /// The identifier that the field is bound to when matching on a variant,
+    /// which is distinct from the generated methods' own parameters and locals.
+    fn binding(&self) -> syn::Ident {
+        match self.member {
+            syn::Member::Named(ref ident) => format_ident!("__field_{}", ident.unraw()),
+            syn::Member::Unnamed(ref index) => format_ident!("__field{}", index.index),
+        }
+    }
 
-            #[inline]
-            #[allow(unused_variables)]
-            #[allow(unused_mut)]
-            fn map_one_query<Q, R, F>(&self, q: &mut Q, mut each: F)
-            where
-                Q: ::scrapmetal::GenericQuery<R>,
-                F: FnMut(&mut Q, R),
-            {
-                match *self {
-                    #( #queries )*
-                }
-            }
+    /// The span of the generated code that traverses the field, so that
+    /// errors, such as the field's type not implementing `Term`, point at it.
+    fn span(&self) -> proc_macro2::Span {
+        match self.traversal {
+            Traversal::With(ref path) => path.span(),
+            Traversal::Mirror(ref mirror) => mirror.span(),
+            _ => self.ty.span(),
+        }
+    }
 
-            #[inline]
-            #[allow(unused_variables)]
-            #[allow(unused_mut)]
-            fn map_one_mutation<M, R, F>(&mut self, m: &mut M, mut each: F)
-            where
-                M: ::scrapmetal::GenericMutate<R>,
-                F: FnMut(&mut M, R),
-            {
-                match *self {
-                    #( #mutations )*
+    /// The expression that transforms the field `value`.
+    fn transform(&self, value: TokenStream) -> TokenStream {
+        let krate = self.krate;
+        let value = respan(value, self.span());
+        match self.traversal {
+            Traversal::Children => quote_spanned! { self.span()=>
+                #krate::export::Field(#value).transform(f)
+            },
+            Traversal::Skip => value,
+            Traversal::Opaque => quote_spanned! { self.span()=>
+                f.transform(#krate::Leaf(#value)).0
+            },
+            Traversal::With(ref path) => quote_spanned! { self.span()=>
+                #path::map_one_transform(#value, f)
+            },
+            Traversal::Mirror(ref mirror) => quote_spanned! { self.span()=>
+                #krate::RemoteField::<<#mirror as #krate::RemoteTerm>::Remote>::map_each(
+                    #value,
+                    |t| f.transform(#krate::Remote::<_, #mirror>::new(t)).0,
+                )
+            },
+        }
+    }
+
+    /// The statements that visit the field `value`, which is a reference to
+    /// the field for all but `Visit::Consume`.
+    fn visit(&self, visit: Visit, value: TokenStream) -> TokenStream {
+        let krate = self.krate;
+        let value = respan(value, self.span());
+        let (visitor, method, with, leaf, each_remote, remote) = match visit {
+            Visit::Query => (
+                quote! { q },
+                quote! { query },
+                quote! { map_one_query },
+                quote! { #krate::Leaf::from_ref(#value) },
+                quote! { for_each_ref },
+                quote! { from_ref },
+            ),
+            Visit::QueryRef => (
+                quote! { q },
+                quote! { query },
+                quote! { map_one_query_ref },
+                quote! { #krate::Leaf::from_ref(#value) },
+                quote! { for_each_ref },
+                quote! { from_ref },
+            ),
+            Visit::Mutation => (
+                quote! { m },
+                quote! { mutate },
+                quote! { map_one_mutation },
+                quote! { #krate::Leaf::from_mut(#value) },
+                quote! { for_each_mut },
+                quote! { from_mut },
+            ),
+            Visit::MutationRef => (
+                quote! { m },
+                quote! { mutate },
+                quote! { map_one_mutation_ref },
+                quote! { #krate::Leaf::from_mut(#value) },
+                quote! { for_each_mut },
+                quote! { from_mut },
+            ),
+            Visit::Consume => (
+                quote! { c },
+                quote! { consume },
+                quote! { map_one_consume },
+                quote! { #krate::Leaf(#value) },
+                quote! { into_each },
+                quote! { new },
+            ),
+        };
+
+        match self.traversal {
+            Traversal::Children => {
+                let field_method = match visit {
+                    Visit::Query => quote! { query },
+                    Visit::QueryRef => quote! { query_ref },
+                    Visit::Mutation => quote! { mutate },
+                    Visit::MutationRef => quote! { mutate_ref },
+                    Visit::Consume => quote! { consume },
+                };
+                quote_spanned! { self.span()=>
+                    let r = #krate::export::Field(#value).#field_method(#visitor);
+                    each(#visitor, r);
                 }
             }
+            Traversal::Skip => quote! {},
+            Traversal::Opaque => quote_spanned! { self.span()=>
+                let r = #visitor.#method(#leaf);
+                each(#visitor, r);
+            },
+            Traversal::With(ref path) => quote_spanned! { self.span()=>
+                #path::#with(#value, #visitor, &mut each);
+            },
+            Traversal::Mirror(ref mirror) => quote_spanned! { self.span()=>
+                #krate::RemoteField::<<#mirror as #krate::RemoteTerm>::Remote>::#each_remote(
+                    #value,
+                    |t| {
+                        let r = #visitor.#method(#krate::Remote::<_, #mirror>::#remote(t));
+                        each(#visitor, r);
+                    },
+                );
+            },
         }
     }
 }
@@ -0,0 +1,149 @@
+//! Dynamically casting values of any type `T` to a `U`, which is what lets
+//! `Transformation`, `Query`, and `Mutation` pick out the values of the one type
+//! they care about.
+//!
+//! There are two backends. By default, casting is built on `core::any::Any`,
+//! which works on stable Rust but only for `'static` types. With the `nightly`
+//! feature enabled, casting is built on specialization instead, and works for
+//! all types.
+
+/// The types that can be dynamically cast to and from. `Term` requires this, as
+/// do the `U` types that `Transformation`, `Query`, and `Mutation` operate on.
+///
+/// By default, this is every `'static` type. With the `nightly` feature
+/// enabled, this is every type.
+#[cfg(not(feature = "nightly"))]
+pub trait Castable: 'static {}
+
+#[cfg(not(feature = "nightly"))]
+impl<T> Castable for T
+where
+    T: 'static + ?Sized,
+{
+}
+
+/// The types that can be dynamically cast to and from. `Term` requires this, as
+/// do the `U` types that `Transformation`, `Query`, and `Mutation` operate on.
+///
+/// By default, this is every `'static` type. With the `nightly` feature
+/// enabled, this is every type.
+#[cfg(feature = "nightly")]
+pub trait Castable {}
+
+#[cfg(feature = "nightly")]
+impl<T> Castable for T
+where
+    T: ?Sized,
+{
+}
+
+pub(crate) use self::backend::{cast, cast_mut, cast_ref};
+
+#[cfg(not(feature = "nightly"))]
+mod backend {
+    use super::Castable;
+    use core::any::{Any, TypeId};
+
+    /// Cast a `T` to a `U`, or give the `T` back if it isn't one.
+    #[inline(always)]
+    pub fn cast<U, T>(t: T) -> Result<U, T>
+    where
+        T: Castable,
+        U: Castable,
+    {
+        if TypeId::of::<T>() == TypeId::of::<U>() {
+            let mut t = Some(t);
+            let u = (&mut t as &mut dyn Any).downcast_mut::<Option<U>>();
+            Ok(u.unwrap().take().unwrap())
+        } else {
+            Err(t)
+        }
+    }
+
+    /// Cast a `&T` to a `&U`, or give the `&T` back if it isn't one.
+    #[inline(always)]
+    pub fn cast_ref<U, T>(t: &T) -> Result<&U, &T>
+    where
+        T: Castable,
+        U: Castable,
+    {
+        match (t as &dyn Any).downcast_ref::<U>() {
+            Some(u) => Ok(u),
+            None => Err(t),
+        }
+    }
+
+    /// Cast a `&mut T` to a `&mut U`, or give the `&mut T` back if it isn't
+    /// one.
+    #[inline(always)]
+    pub fn cast_mut<U, T>(t: &mut T) -> Result<&mut U, &mut T>
+    where
+        T: Castable,
+        U: Castable,
+    {
+        if TypeId::of::<T>() == TypeId::of::<U>() {
+            Ok((t as &mut dyn Any).downcast_mut::<U>().unwrap())
+        } else {
+            Err(t)
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod backend {
+    /// Dynamically cast a value to a `T`.
+    trait Cast<T>: Sized {
+        fn cast(self) -> Result<T, Self>;
+    }
+
+    /// A default blanket implementation that says the value cannot be cast to
+    /// `T`.
+    impl<T, U> Cast<T> for U {
+        #[inline(always)]
+        default fn cast(self) -> Result<T, Self> {
+            Err(self)
+        }
+    }
+
+    /// A specialization for when `Self=T` that allows the cast to succeed.
+    impl<T> Cast<T> for T {
+        #[inline(always)]
+        fn cast(self) -> Result<T, Self> {
+            Ok(self)
+        }
+    }
+
+    /// Cast a `T` to a `U`, or give the `T` back if it isn't one.
+    #[inline(always)]
+    pub fn cast<U, T>(t: T) -> Result<U, T> {
+        Cast::<U>::cast(t)
+    }
+
+    /// Cast a `&T` to a `&U`, or give the `&T` back if it isn't one.
+    #[inline(always)]
+    pub fn cast_ref<U, T>(t: &T) -> Result<&U, &T> {
+        Cast::<&U>::cast(t)
+    }
+
+    /// Cast a `&mut T` to a `&mut U`, or give the `&mut T` back if it isn't
+    /// one.
+    #[inline(always)]
+    pub fn cast_mut<U, T>(t: &mut T) -> Result<&mut U, &mut T> {
+        Cast::<&mut U>::cast(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn casting() {
+        assert_eq!(cast::<bool, _>(1), Err(1));
+        assert_eq!(cast::<bool, _>(true), Ok(true));
+        assert_eq!(cast_ref::<bool, _>(&1), Err(&1));
+        assert_eq!(cast_ref::<bool, _>(&true), Ok(&true));
+        assert_eq!(cast_mut::<bool, _>(&mut 1), Err(&mut 1));
+        assert_eq!(cast_mut::<bool, _>(&mut true), Ok(&mut true));
+    }
+}
@@ -0,0 +1,11 @@
+use super::Term;
+
+/// A similar work around as `GenericTransform`, but taking ownership of the
+/// value and returning some query type, rather than the same type. This is
+/// roughly equivalent to `for<T> FnMut(T) -> R`.
+pub trait GenericConsume<R> {
+    /// Call the consume function on any `T`.
+    fn consume<T>(&mut self, t: T) -> R
+    where
+        T: Term;
+}
@@ -0,0 +1,188 @@
+use super::{Castable, GenericConsume, GenericQueryRef, Term};
+use alloc::vec::{self, Vec};
+use cast::{cast, cast_ref};
+use core::fmt;
+use core::marker::PhantomData;
+
+/// A type-erased `Term` that is borrowed for `'a`, so that the values of many
+/// different types can live together on `Descendants`' stack.
+trait Node<'a, U> {
+    /// Push this value's direct children onto the stack, such that the first
+    /// child is on top.
+    fn push_children(&'a self, stack: &mut Vec<&'a dyn Node<'a, U>>);
+
+    /// Cast this value to a `U`, if it is one.
+    fn cast(&'a self) -> Option<&'a U>;
+}
+
+impl<'a, T, U> Node<'a, U> for T
+where
+    T: Term,
+    U: 'a + Castable,
+{
+    #[inline]
+    fn push_children(&'a self, stack: &mut Vec<&'a dyn Node<'a, U>>) {
+        let start = stack.len();
+        self.map_one_query_ref(&mut ToNode(PhantomData), |_, node| {
+            stack.push(node);
+        });
+        stack[start..].reverse();
+    }
+
+    #[inline]
+    fn cast(&'a self) -> Option<&'a U> {
+        cast_ref::<U, _>(self).ok()
+    }
+}
+
+/// Erase each direct child into a `Node`.
+struct ToNode<U>(PhantomData<fn() -> U>);
+
+impl<'a, U> GenericQueryRef<'a, &'a dyn Node<'a, U>> for ToNode<U>
+where
+    U: 'a + Castable,
+{
+    #[inline]
+    fn query<T>(&mut self, t: &'a T) -> &'a dyn Node<'a, U>
+    where
+        T: Term,
+    {
+        t
+    }
+}
+
+/// A lazy iterator over references to every `U` within a value.
+///
+/// Values are visited in a top-down, left-to-right order. Only the values that
+/// have been visited so far are traversed, so stopping early (for example, with
+/// `take` or `find`) skips the rest of the data structure. The values inside
+/// cells and locks are not visited, and an `Inaccessible::Interior` is visited
+/// in place of each.
+///
+/// This `struct` is created by `Term::descendants`.
+pub struct Descendants<'a, U>
+where
+    U: 'a,
+{
+    stack: Vec<&'a dyn Node<'a, U>>,
+}
+
+impl<'a, U> Descendants<'a, U>
+where
+    U: 'a + Castable,
+{
+    #[inline]
+    pub(crate) fn new<T>(t: &'a T) -> Descendants<'a, U>
+    where
+        T: Term,
+    {
+        Descendants { stack: vec![t] }
+    }
+}
+
+impl<'a, U> Iterator for Descendants<'a, U>
+where
+    U: 'a,
+{
+    type Item = &'a U;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a U> {
+        while let Some(node) = self.stack.pop() {
+            node.push_children(&mut self.stack);
+            if let Some(u) = node.cast() {
+                return Some(u);
+            }
+        }
+        None
+    }
+}
+
+impl<'a, U> fmt::Debug for Descendants<'a, U>
+where
+    U: 'a,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Descendants")
+            .field("pending", &self.stack.len())
+            .finish()
+    }
+}
+
+/// Move every outermost `U` out of a value.
+struct MoveOut<U>(Vec<U>);
+
+impl<U> GenericConsume<()> for MoveOut<U>
+where
+    U: Castable,
+{
+    #[inline]
+    fn consume<T>(&mut self, t: T)
+    where
+        T: Term,
+    {
+        match cast::<U, _>(t) {
+            Ok(u) => self.0.push(u),
+            Err(t) => t.map_one_consume(self, |_, ()| {}),
+        }
+    }
+}
+
+/// An iterator over every `U` that was moved out of a consumed value.
+///
+/// Values are yielded in a top-down, left-to-right order. Unlike `Descendants`,
+/// the whole value is taken apart up front, since the parts that are not a `U`
+/// must be dropped before the first `U` can be yielded.
+///
+/// This `struct` is created by `Term::into_descendants`.
+#[derive(Debug)]
+pub struct IntoDescendants<U> {
+    iter: vec::IntoIter<U>,
+}
+
+impl<U> IntoDescendants<U>
+where
+    U: Castable,
+{
+    #[inline]
+    pub(crate) fn new<T>(t: T) -> IntoDescendants<U>
+    where
+        T: Term,
+    {
+        let mut move_out = MoveOut(vec![]);
+        move_out.consume(t);
+        IntoDescendants {
+            iter: move_out.0.into_iter(),
+        }
+    }
+}
+
+impl<U> Iterator for IntoDescendants<U> {
+    type Item = U;
+
+    #[inline]
+    fn next(&mut self) -> Option<U> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descendants() {
+        let v = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        let odd: Vec<_> = v.descendants::<i32>().filter(|i| *i % 2 == 1).collect();
+        assert_eq!(odd, vec![&1, &3]);
+        assert_eq!(v.descendants::<(i32, char)>().nth(1), Some(&(2, 'b')));
+
+        let chars: Vec<_> = v.into_descendants::<char>().collect();
+        assert_eq!(chars, vec!['a', 'b', 'c']);
+    }
+}
@@ -0,0 +1,214 @@
+//! Support for the code that `#[derive(Term)]` generates to visit fields.
+//!
+//! The derive asserts that each traversed field's type is a `Term` once, with
+//! `assert_field_term`, and visits the field by calling methods on a `Field`
+//! wrapping it. Those resolve to the `TermField*` traits when the type is a
+//! `Term`, and to `NotTermField` otherwise, so that a missing implementation
+//! is only reported by the assertion rather than again by every method.
+//!
+//! Likewise, the derive implements `Term::clone_term` by calling a method on a
+//! `CloneProbe`, which clones the value only when its type is `Clone`.
+
+use super::{GenericConsume, GenericMutate, GenericMutateRef, GenericQuery, GenericQueryRef,
+            GenericTransform, Term};
+
+/// Implemented for every `Term`, to report a field type that is not one with
+/// a single error pointing at the field.
+#[diagnostic::on_unimplemented(
+    message = "the field type `{Self}` does not implement `Term`",
+    label = "not a `Term`",
+    note = "implement `Term` for it, or mark the field with `#[term(skip)]`, \
+            `#[term(opaque)]`, or `#[term(with = \"...\")]`"
+)]
+pub trait FieldTerm {}
+
+#[diagnostic::do_not_recommend]
+impl<T: Term> FieldTerm for T {}
+
+/// Fail to compile unless `T` is a `Term`.
+#[inline]
+pub fn assert_field_term<T: FieldTerm>() {}
+
+/// A field, or a reference to one, being visited.
+#[derive(Debug)]
+pub struct Field<T>(pub T);
+
+/// Visiting a field by value.
+pub trait TermField<T> {
+    /// Transform the field.
+    fn transform<F>(self, f: &mut F) -> T
+    where
+        F: GenericTransform;
+
+    /// Consume the field.
+    fn consume<C, R>(self, c: &mut C) -> R
+    where
+        C: GenericConsume<R>;
+}
+
+impl<T: Term> TermField<T> for Field<T> {
+    #[inline]
+    fn transform<F>(self, f: &mut F) -> T
+    where
+        F: GenericTransform,
+    {
+        f.transform(self.0)
+    }
+
+    #[inline]
+    fn consume<C, R>(self, c: &mut C) -> R
+    where
+        C: GenericConsume<R>,
+    {
+        c.consume(self.0)
+    }
+}
+
+/// Visiting a field by reference.
+pub trait TermFieldRef<'a> {
+    /// Query the field.
+    fn query<Q, R>(self, q: &mut Q) -> R
+    where
+        Q: GenericQuery<R>;
+
+    /// Query the field, keeping the reference.
+    fn query_ref<Q, R>(self, q: &mut Q) -> R
+    where
+        Q: GenericQueryRef<'a, R>;
+}
+
+impl<'a, T: Term> TermFieldRef<'a> for Field<&'a T> {
+    #[inline]
+    fn query<Q, R>(self, q: &mut Q) -> R
+    where
+        Q: GenericQuery<R>,
+    {
+        q.query(self.0)
+    }
+
+    #[inline]
+    fn query_ref<Q, R>(self, q: &mut Q) -> R
+    where
+        Q: GenericQueryRef<'a, R>,
+    {
+        q.query(self.0)
+    }
+}
+
+/// Visiting a field by mutable reference.
+pub trait TermFieldMut<'a> {
+    /// Mutate the field.
+    fn mutate<M, R>(self, m: &mut M) -> R
+    where
+        M: GenericMutate<R>;
+
+    /// Mutate the field, keeping the reference.
+    fn mutate_ref<M, R>(self, m: &mut M) -> R
+    where
+        M: GenericMutateRef<'a, R>;
+}
+
+impl<'a, T: Term> TermFieldMut<'a> for Field<&'a mut T> {
+    #[inline]
+    fn mutate<M, R>(self, m: &mut M) -> R
+    where
+        M: GenericMutate<R>,
+    {
+        m.mutate(self.0)
+    }
+
+    #[inline]
+    fn mutate_ref<M, R>(self, m: &mut M) -> R
+    where
+        M: GenericMutateRef<'a, R>,
+    {
+        m.mutate(self.0)
+    }
+}
+
+/// Visiting a field whose type is not a `Term`, which `assert_field_term` has
+/// already rejected, so these are never called.
+pub trait NotTermField<T> {
+    /// Never called.
+    fn transform<F>(self, _: &mut F) -> T
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    /// Never called.
+    fn consume<C, R>(self, _: &mut C) -> R
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    /// Never called.
+    fn query<Q, R>(self, _: &mut Q) -> R
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    /// Never called.
+    fn query_ref<Q, R>(self, _: &mut Q) -> R
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    /// Never called.
+    fn mutate<M, R>(self, _: &mut M) -> R
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    /// Never called.
+    fn mutate_ref<M, R>(self, _: &mut M) -> R
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+}
+
+impl<T> NotTermField<T> for &Field<T> {}
+
+/// A value being cloned for `Term::clone_term`. The `TermClone` method
+/// resolves when the value's type is `Clone`, and the `NotTermClone` method
+/// otherwise, including when that depends on a type parameter.
+#[derive(Debug)]
+pub struct CloneProbe<'a, T: 'a>(pub &'a T);
+
+/// Cloning a value whose type is `Clone`.
+pub trait TermClone<T> {
+    /// Clone the value.
+    fn clone_term(self) -> Option<T>;
+}
+
+impl<'a, T: Clone> TermClone<T> for CloneProbe<'a, T> {
+    #[inline]
+    fn clone_term(self) -> Option<T> {
+        Some(self.0.clone())
+    }
+}
+
+/// Cloning a value whose type is not known to be `Clone`, which fails.
+pub trait NotTermClone<T> {
+    /// Give up.
+    #[inline]
+    fn clone_term(self) -> Option<T>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+impl<'a, 'b, T> NotTermClone<T> for &'b CloneProbe<'a, T> {}
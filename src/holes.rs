@@ -0,0 +1,249 @@
+use super::{Castable, GenericQueryRef, GenericTransform, Inaccessible, Term};
+use alloc::vec::Vec;
+use cast::{cast, cast_ref};
+use core::fmt;
+
+/// A `U` within some value of type `T`, along with the context needed to plug
+/// a replacement `U` back into its place.
+///
+/// This `struct` is created by `Term::holes`. There are no holes inside a
+/// `Cell`, `RefCell`, `Mutex`, or `RwLock`.
+pub struct Hole<'a, T, U>
+where
+    T: 'a,
+    U: 'a,
+{
+    term: &'a T,
+    value: &'a U,
+    path: Vec<usize>,
+}
+
+impl<'a, T, U> Hole<'a, T, U>
+where
+    T: 'a + Term,
+    U: 'a + Castable,
+{
+    /// Get the `U` currently in this hole.
+    #[inline]
+    pub fn get(&self) -> &'a U {
+        self.value
+    }
+
+    /// Get the path from the root value to this hole, as the index of the
+    /// direct child to descend into at each step.
+    #[inline]
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Rebuild the whole value, with `u` plugged into this hole in place of the
+    /// `U` that was there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hole cannot be filled. See `try_fill`.
+    #[inline]
+    pub fn fill(&self, u: U) -> T
+    where
+        T: Clone,
+    {
+        match self.try_fill(u) {
+            Ok(t) => t,
+            Err(_) => panic!(
+                "the hole at {:?} is inside a value that transformations treat as a leaf, \
+                 and cannot be filled",
+                self.path
+            ),
+        }
+    }
+
+    /// Like `fill`, but gives `u` back if the hole cannot be filled.
+    ///
+    /// Holes are found by querying, and filled by transforming, and a few
+    /// `Term`s can be queried but not transformed: a borrowed slice's elements
+    /// cannot be replaced, nor can the value behind a shared `Rc` or `Arc` that
+    /// cannot be cloned. Holes inside them cannot be filled.
+    #[inline]
+    pub fn try_fill(&self, u: U) -> Result<T, U>
+    where
+        T: Clone,
+    {
+        let mut plug = Plug {
+            path: &self.path,
+            value: Some(u),
+        };
+        let t = plug.transform(self.term.clone());
+        match plug.value {
+            None => Ok(t),
+            Some(u) => Err(u),
+        }
+    }
+}
+
+impl<'a, T, U> fmt::Debug for Hole<'a, T, U>
+where
+    U: 'a + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hole")
+            .field("value", self.value)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Find every `U` within a value, and the path of child indices leading to it.
+pub(crate) struct FindHoles<'a, U>
+where
+    U: 'a,
+{
+    pub(crate) found: Vec<(&'a U, Vec<usize>)>,
+    path: Vec<usize>,
+}
+
+impl<'a, U> FindHoles<'a, U>
+where
+    U: 'a,
+{
+    #[inline]
+    pub(crate) fn new() -> FindHoles<'a, U> {
+        FindHoles {
+            found: vec![],
+            path: vec![],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn into_holes<T>(self, term: &'a T) -> Vec<Hole<'a, T, U>> {
+        self.found
+            .into_iter()
+            .map(|(value, path)| Hole { term, value, path })
+            .collect()
+    }
+}
+
+impl<'a, U> GenericQueryRef<'a, ()> for FindHoles<'a, U>
+where
+    U: 'a + Castable,
+{
+    #[inline]
+    fn query<T>(&mut self, t: &'a T)
+    where
+        T: Term,
+    {
+        // The marker stands in for a value that `Plug` cannot reach.
+        if let Ok(&Inaccessible::Interior) = cast_ref::<Inaccessible, _>(t) {
+            return;
+        }
+
+        if let Ok(u) = cast_ref::<U, _>(t) {
+            self.found.push((u, self.path.clone()));
+        }
+
+        self.path.push(0);
+        t.map_one_query_ref(self, |me, ()| {
+            *me.path.last_mut().unwrap() += 1;
+        });
+        self.path.pop();
+    }
+}
+
+/// Follow a path of child indices down to a hole, and plug a value into it.
+/// The value is left in place if the path leads somewhere else, because a
+/// transformation visits different children than the query that found it.
+struct Plug<'p, U> {
+    path: &'p [usize],
+    value: Option<U>,
+}
+
+impl<'p, U> GenericTransform for Plug<'p, U>
+where
+    U: Castable,
+{
+    #[inline]
+    fn transform<T>(&mut self, t: T) -> T
+    where
+        T: Term,
+    {
+        match self.path.split_first() {
+            Some((&n, rest)) => {
+                self.path = rest;
+                t.map_nth_transform(n, self)
+            }
+            None => match self.value.take() {
+                Some(u) => match cast::<T, _>(u) {
+                    Ok(u) => u,
+                    Err(u) => {
+                        self.value = Some(u);
+                        t
+                    }
+                },
+                None => t,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holes() {
+        let v = vec![(1, 'a'), (2, 'b')];
+        let holes = v.holes::<char>();
+        assert_eq!(holes.len(), 2);
+        assert_eq!(holes[1].get(), &'b');
+        assert_eq!(holes[1].path(), &[1, 1]);
+        assert_eq!(holes[1].fill('z'), vec![(1, 'a'), (2, 'z')]);
+
+        let holes = v.holes::<Vec<(i32, char)>>();
+        assert_eq!(holes.len(), 1);
+        assert_eq!(holes[0].fill(vec![]), vec![]);
+    }
+
+    #[test]
+    fn holes_through_shared_pointers() {
+        use alloc::rc::Rc;
+        use alloc::sync::Arc;
+
+        let v = (Rc::new(1), Arc::new((2, 3)));
+        let holes = v.holes::<i32>();
+        assert_eq!(holes.len(), 3);
+        assert_eq!(holes[0].path(), &[0, 0]);
+
+        let (a, b) = holes[0].fill(5);
+        assert_eq!((*a, *b), (5, (2, 3)));
+        let (a, b) = holes[2].fill(6);
+        assert_eq!((*a, *b), (1, (2, 6)));
+        assert_eq!((*v.0, *v.1), (1, (2, 3)));
+    }
+
+    #[test]
+    fn holes_inside_borrowed_slices() {
+        static SLICE: &[i32] = &[1, 2, 3];
+        let v = (0, SLICE);
+        let holes = v.holes::<i32>();
+        assert_eq!(holes.len(), 4);
+        assert_eq!(holes[0].fill(5), (5, SLICE));
+        assert_eq!(holes[2].get(), &2);
+        assert_eq!(holes[2].try_fill(99), Err(99));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be filled")]
+    fn filling_unreachable_holes() {
+        static SLICE: &[i32] = &[1, 2, 3];
+        (0, SLICE).holes::<i32>()[2].fill(99);
+    }
+
+    #[test]
+    fn no_holes_inside_cells() {
+        use core::cell::RefCell;
+
+        let v = (RefCell::new(1), 2);
+        assert_eq!(v.holes::<i32>().len(), 1);
+        assert_eq!(v.holes::<Inaccessible>().len(), 0);
+        assert_eq!(v.holes::<i32>()[0].fill(3).1, 3);
+    }
+}
@@ -0,0 +1,27 @@
+/// Stands in for the contents of a pointer, cell, or lock that could not be
+/// reached without panicking or blocking.
+///
+/// Traversals visit an `Inaccessible` leaf in place of the value inside an
+/// `Rc` or `Arc` that is shared and cannot be cloned, or a `RefCell`, `Mutex`,
+/// or `RwLock` that is already borrowed, locked, or poisoned, and leave that
+/// value untouched. `map_one_query_ref` always visits an
+/// `Inaccessible::Interior` in place of the value inside a `Cell`, `RefCell`,
+/// `Mutex`, or `RwLock`. Query or mutate `Inaccessible` values to find out
+/// whether a traversal was complete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Inaccessible {
+    /// An `Rc` or `Arc` that was shared with other pointers, and whose value
+    /// could neither be cloned nor mutated through the shared pointer.
+    Shared,
+    /// A `RefCell` that was already mutably borrowed.
+    Borrowed,
+    /// A `Mutex` or `RwLock` that was locked by someone else.
+    Locked,
+    /// A `Mutex` or `RwLock` that was poisoned by a panic while locked.
+    Poisoned,
+    /// A `Cell`, `RefCell`, `Mutex`, or `RwLock`, whose value cannot be
+    /// borrowed for as long as the cell or lock itself. Visited by
+    /// `map_one_query_ref`, and so by `Term::descendants`, `Term::holes`,
+    /// `EverythingRef`, and the `Visit` traits that `define_visitors!` defines.
+    Interior,
+}
@@ -0,0 +1,117 @@
+//! Traversing the keys of maps and sets.
+//!
+//! On their own, `HashMap` and `BTreeMap` keep their keys fixed, and only
+//! transform and mutate their values, in place. Changing a key means moving its
+//! entry, and two keys may change into the same one. `HashSet` and `BTreeSet`
+//! do transform and mutate their elements, but silently merge the ones that
+//! become equal. Wrap the collection in `Keyed` to traverse a map's keys, and
+//! to find out about any such collisions.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A map or set whose keys are traversed, too.
+///
+/// Each entry of a keyed map is traversed as its key followed by its value.
+/// Transforming or mutating a keyed collection rebuilds it, and an entry whose
+/// new key is already in the rebuilt collection is set aside as a collision,
+/// rather than replacing the entry that got there first. Collisions are not
+/// traversed again.
+///
+/// `map_one_mutation_ref` cannot rebuild the collection, so it only visits the
+/// values of a keyed map, and nothing in a keyed set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Keyed<C>
+where
+    C: IntoIterator,
+{
+    collection: C,
+    collisions: Vec<C::Item>,
+}
+
+impl<C> Keyed<C>
+where
+    C: IntoIterator,
+{
+    /// Traverse the keys of `collection`, too.
+    #[inline]
+    pub fn new(collection: C) -> Keyed<C> {
+        Keyed {
+            collection,
+            collisions: vec![],
+        }
+    }
+
+    /// Get the collection.
+    #[inline]
+    pub fn collection(&self) -> &C {
+        &self.collection
+    }
+
+    /// Get the entries that were set aside because their key collided with
+    /// that of another entry, in the order they were found.
+    #[inline]
+    pub fn collisions(&self) -> &[C::Item] {
+        &self.collisions
+    }
+
+    /// Unwrap the collection, or return an error if any keys collided.
+    #[inline]
+    pub fn into_inner(self) -> Result<C, KeyCollision<C>> {
+        if self.collisions.is_empty() {
+            Ok(self.collection)
+        } else {
+            Err(KeyCollision {
+                collection: self.collection,
+                collisions: self.collisions,
+            })
+        }
+    }
+
+    #[inline]
+    pub(crate) fn parts_mut(&mut self) -> (&mut C, &mut Vec<C::Item>) {
+        (&mut self.collection, &mut self.collisions)
+    }
+
+    #[inline]
+    pub(crate) fn into_parts(self) -> (C, Vec<C::Item>) {
+        (self.collection, self.collisions)
+    }
+
+    #[inline]
+    pub(crate) fn from_parts(collection: C, collisions: Vec<C::Item>) -> Keyed<C> {
+        Keyed {
+            collection,
+            collisions,
+        }
+    }
+}
+
+/// The error returned by `Keyed::into_inner` when keys collided.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyCollision<C>
+where
+    C: IntoIterator,
+{
+    /// The rebuilt collection, holding the first entry found for each key.
+    pub collection: C,
+    /// The entries whose keys collided with an entry in `collection`.
+    pub collisions: Vec<C::Item>,
+}
+
+impl<C> fmt::Display for KeyCollision<C>
+where
+    C: IntoIterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} entries had colliding keys", self.collisions.len())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C> ::std::error::Error for KeyCollision<C>
+where
+    C: IntoIterator + fmt::Debug,
+    C::Item: fmt::Debug,
+{
+}
@@ -0,0 +1,115 @@
+//! Wrappers for embedding types that do not implement `Term` inside types that
+//! do.
+//!
+//! There is no blanket "every type is a leaf" fallback: on stable Rust it would
+//! conflict with every other `Term` implementation, and even with
+//! specialization it would keep downstream crates from implementing `Term` for
+//! their own types. Wrap the field instead.
+
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// A leaf value that is never traversed into.
+///
+/// `Leaf<T>` is a `Term` with no children for any `T`, so it can hold a type
+/// that does not implement `Term`, such as a type from another crate, as a
+/// field of a type that derives `Term`. Transformations and queries still see
+/// the `Leaf<T>` itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Leaf<T>(pub T);
+
+/// Like `Leaf`, but also opaque to `Debug`.
+///
+/// `Opaque<T>` is `Debug` even when `T` is not, which makes it a fit for
+/// handles and other values with nothing useful to print.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Opaque<T>(pub T);
+
+macro_rules! impl_wrapper {
+    ( $name:ident ) => {
+        impl<T> $name<T> {
+            /// Unwrap the inner value.
+            #[inline]
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+
+            /// View a reference to a value as a reference to the wrapped value.
+            #[inline]
+            pub fn from_ref(t: &T) -> &$name<T> {
+                // Safe because `$name<T>` is `repr(transparent)` over `T`.
+                unsafe { &*(t as *const T as *const $name<T>) }
+            }
+
+            /// View a mutable reference to a value as a mutable reference to
+            /// the wrapped value.
+            #[inline]
+            pub fn from_mut(t: &mut T) -> &mut $name<T> {
+                // Safe because `$name<T>` is `repr(transparent)` over `T`.
+                unsafe { &mut *(t as *mut T as *mut $name<T>) }
+            }
+        }
+
+        impl<T> From<T> for $name<T> {
+            #[inline]
+            fn from(t: T) -> $name<T> {
+                $name(t)
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            #[inline]
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+    }
+}
+
+impl_wrapper!(Leaf);
+impl_wrapper!(Opaque);
+
+impl<T> fmt::Debug for Opaque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Opaque(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::*;
+
+    struct Handle(u32);
+
+    #[test]
+    fn leaves() {
+        let mut double = Everywhere::new(Transformation::new(|i: i32| i * 2));
+        let (leaf, opaque, i) = double.transform((Leaf(3), Opaque(Handle(4)), 5));
+        assert_eq!((leaf, opaque.0 .0, i), (Leaf(3), 4, 10));
+
+        let mut count = Everything::new(Query::new(|_: &Opaque<Handle>| 1), |a, b| a + b);
+        assert_eq!(count.query(&(Opaque(Handle(1)), (Opaque(Handle(2)), 3))), 2);
+
+        let mut handle = Handle(6);
+        Opaque::from_mut(&mut handle).0 .0 += 1;
+        assert_eq!(Opaque::from_ref(&handle).0 .0, 7);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn opaque_debug() {
+        assert_eq!(format!("{:?}", Opaque(Handle(5))), "Opaque(..)");
+    }
+}
@@ -4,42 +4,93 @@
 //! combinators from the Haskell paper "Scrap Your Boilerplate: A Practical
 //! Design Pattern for Generic Programming" by Lämmel and Peyton Jones to Rust.
 //!
-#![feature(specialization)]
+//! By default, this crate works on stable Rust, but requires that every `Term`
+//! be `'static`. Enabling the `nightly` feature uses specialization instead,
+//! which lifts that restriction but requires a nightly Rust compiler. See
+//! `Castable` for details.
+//!
+//! This crate is `no_std`. The `Term` implementations for `Vec`, `Box`, and the
+//! other collections in `alloc`, as well as the iterators and helpers that need
+//! to allocate, require the `alloc` feature. The `Term` implementations for
+//! `HashMap` and `HashSet` require the `std` feature, which is enabled by
+//! default and implies `alloc`.
+//!
+//! With the `derive` feature, this crate re-exports `#[derive(Term)]` from
+//! `scrapmetal-derive`, so `use scrapmetal::Term;` imports both the trait and
+//! the derive.
+#![no_std]
+#![cfg_attr(feature = "nightly", feature(specialization))]
+#![cfg_attr(feature = "nightly", allow(incomplete_features))]
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
 
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "derive")]
+extern crate scrapmetal_derive;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[macro_use]
+mod macros;
+
+mod cast;
+mod consume;
+#[cfg(feature = "alloc")]
+mod descendants;
+mod field;
+#[cfg(feature = "alloc")]
+mod holes;
+mod inaccessible;
+#[cfg(feature = "alloc")]
+mod keyed;
+mod leaf;
 mod mutation;
 mod query;
+mod remote;
+#[cfg(feature = "std")]
+mod sorted;
 mod term_impls;
 mod transform;
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use holes::FindHoles;
+#[cfg(feature = "alloc")]
+use mutation::CollectMut;
+use transform::Nth;
+
+pub use cast::Castable;
+#[cfg(feature = "derive")]
+pub use scrapmetal_derive::Term;
+pub use consume::*;
+#[cfg(feature = "alloc")]
+pub use descendants::*;
+#[cfg(feature = "alloc")]
+pub use holes::*;
+pub use inaccessible::*;
+#[cfg(feature = "alloc")]
+pub use keyed::*;
+pub use leaf::*;
 pub use mutation::*;
 pub use query::*;
+pub use remote::*;
+#[cfg(feature = "std")]
+pub use sorted::*;
 pub use transform::*;
 
-/// Dynamically cast a value to a `T`.
-trait Cast<T>: Sized {
-    fn cast(self) -> Result<T, Self>;
-}
-
-/// A default blanket implementation that says the value cannot be cast to `T`.
-impl<T, U> Cast<T> for U {
-    #[inline(always)]
-    default fn cast(self) -> Result<T, Self> {
-        Err(self)
-    }
-}
-
-/// A specialization for when `Self=T` that allows the cast to succeed.
-impl<T> Cast<T> for T {
-    #[inline(always)]
-    fn cast(self) -> Result<T, Self> {
-        Ok(self)
-    }
+#[doc(hidden)]
+pub mod export {
+    pub use core::iter::FromIterator;
+    pub use core::marker::PhantomData;
+    pub use field::{assert_field_term, CloneProbe, Field, FieldTerm, NotTermClone, NotTermField,
+                    TermClone, TermField, TermFieldMut, TermFieldRef};
 }
 
 /// A `Term` is a value that can be mapped or queried.
-pub trait Term: Sized {
+pub trait Term: Sized + Castable {
     /// Perform one-layer traversal and transformation of this value's direct
     /// children.
     fn map_one_transform<F>(self, f: &mut F) -> Self
@@ -54,6 +105,17 @@ pub trait Term: Sized {
         Q: GenericQuery<R>,
         F: FnMut(&mut Q, R);
 
+    /// Like `map_one_query`, but each direct child is given to the query with
+    /// the same lifetime as `self`, so that query results may borrow from it.
+    ///
+    /// The value inside a `Cell`, `RefCell`, `Mutex`, or `RwLock` cannot be
+    /// borrowed for that long, so an `Inaccessible::Interior` is given to the
+    /// query in its place.
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R);
+
     /// Perform one-layer traversal and mutable querying of this value's direct
     /// children, calling `each` on each of the query result for each direct
     /// child.
@@ -61,15 +123,121 @@ pub trait Term: Sized {
     where
         M: GenericMutate<R>,
         F: FnMut(&mut M, R);
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Like `map_one_mutation`, but each direct child is given to the mutation
+    /// with the same lifetime as `self`, so that mutation results may borrow
+    /// from it.
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R);
+
+    /// Perform one-layer traversal of this value's direct children by value,
+    /// consuming this value and calling `each` on the result of consuming each
+    /// direct child.
+    fn map_one_consume<C, R, F>(self, consume: &mut C, each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R);
 
-    #[test]
-    fn casting() {
-        assert_eq!(Cast::<bool>::cast(1), Err(1));
-        assert_eq!(Cast::<bool>::cast(true), Ok(true));
+    /// Clone this value, so that a value behind a shared `Rc` or `Arc` can be
+    /// cloned-on-write.
+    ///
+    /// The default returns `None`, as do cells and locks, since a clone of one
+    /// would no longer share its value. `#[derive(Term)]` and `impl_term!`
+    /// clone the value whenever its type is known to be `Clone`.
+    #[inline]
+    fn clone_term(&self) -> Option<Self> {
+        None
+    }
+
+    /// Like `map_one_mutation`, but through a shared reference, for a value
+    /// behind a shared `Rc` or `Arc`. Returns whether this value's children
+    /// could be mutated that way.
+    ///
+    /// The default visits nothing and returns `false`. A `Cell`, `RefCell`,
+    /// `Mutex`, or `RwLock` mutates its value, visiting an `Inaccessible` in
+    /// its place if it is already borrowed, locked, or poisoned.
+    #[inline]
+    fn map_one_mutation_shared<M, R, F>(&self, _: &mut M, _: F) -> bool
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        false
+    }
+
+    /// Perform one-layer traversal and transformation of only this value's
+    /// `n`th direct child, counting in the order that `map_one_transform`
+    /// visits them. The other children are left untouched.
+    #[inline]
+    fn map_nth_transform<F>(self, n: usize, f: &mut F) -> Self
+    where
+        F: GenericTransform,
+    {
+        self.map_one_transform(&mut Nth::new(n, f))
+    }
+
+    /// Lazily iterate over every `U` within this value, including this value
+    /// itself, in a top-down, left-to-right order.
+    ///
+    /// This is built on `map_one_query_ref`, so it does not see inside cells
+    /// and locks, and yields an `Inaccessible::Interior` for each instead.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn descendants<'a, U>(&'a self) -> Descendants<'a, U>
+    where
+        U: 'a + Castable,
+    {
+        Descendants::new(self)
+    }
+
+    /// Consume this value and iterate over every `U` that was within it, in a
+    /// top-down, left-to-right order.
+    ///
+    /// A `U` nested within another `U` is moved out as part of its outer `U`,
+    /// and is not yielded on its own.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn into_descendants<U>(self) -> IntoDescendants<U>
+    where
+        U: Castable,
+    {
+        IntoDescendants::new(self)
+    }
+
+    /// Collect mutable references to every `U` within this value, including
+    /// this value itself, in a top-down, left-to-right order.
+    ///
+    /// A `U` nested within another `U` is only reachable through its outer
+    /// `U`'s reference, and is not collected on its own.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn collect_mut<'a, U>(&'a mut self) -> Vec<&'a mut U>
+    where
+        U: 'a + Castable,
+    {
+        let mut collect = CollectMut(vec![]);
+        collect.mutate(self);
+        collect.0
+    }
+
+    /// Find every `U` within this value, including this value itself, in a
+    /// top-down, left-to-right order. Each `Hole` can rebuild this whole value
+    /// with a replacement plugged in where its `U` was.
+    ///
+    /// This is built on `map_one_query_ref`, so it does not find any `U`
+    /// inside a `Cell`, `RefCell`, `Mutex`, or `RwLock`. It does find those
+    /// inside a borrowed slice, which `Hole::fill` cannot replace, since
+    /// transformations treat borrowed slices as leaves. See `Hole::try_fill`.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn holes<'a, U>(&'a self) -> Vec<Hole<'a, Self, U>>
+    where
+        U: 'a + Castable,
+    {
+        let mut find = FindHoles::new();
+        find.query(self);
+        find.into_holes(self)
     }
 }
@@ -0,0 +1,739 @@
+/// Implement `Term` for a collection type.
+///
+/// For a sequence or set, give its generic parameters in brackets, followed by
+/// the type. The collection must implement `IntoIterator` and `FromIterator`
+/// for its elements, and `IntoIterator` by reference and by mutable reference.
+/// Each element is a direct child.
+///
+/// For a map, start with `map`. The map must implement `IntoIterator` and
+/// `FromIterator` for `(K, V)` pairs, and `IntoIterator` by reference and by
+/// mutable reference, yielding `(&K, &V)` and `(&K, &mut V)` pairs. Like the
+/// standard library's maps, keys are fixed, and each value is a direct child.
+/// The map's key and value types must be named `K` and `V`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate scrapmetal;
+///
+/// use scrapmetal::*;
+///
+/// pub struct Stack<T>(Vec<T>);
+/// pub struct Table<K, V>(Vec<(K, V)>);
+/// # impl<T> IntoIterator for Stack<T> {
+/// #     type Item = T;
+/// #     type IntoIter = ::std::vec::IntoIter<T>;
+/// #     fn into_iter(self) -> Self::IntoIter { self.0.into_iter() }
+/// # }
+/// # impl<'a, T> IntoIterator for &'a Stack<T> {
+/// #     type Item = &'a T;
+/// #     type IntoIter = ::std::slice::Iter<'a, T>;
+/// #     fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+/// # }
+/// # impl<'a, T> IntoIterator for &'a mut Stack<T> {
+/// #     type Item = &'a mut T;
+/// #     type IntoIter = ::std::slice::IterMut<'a, T>;
+/// #     fn into_iter(self) -> Self::IntoIter { self.0.iter_mut() }
+/// # }
+/// # impl<T> ::std::iter::FromIterator<T> for Stack<T> {
+/// #     fn from_iter<I: IntoIterator<Item = T>>(i: I) -> Self { Stack(i.into_iter().collect()) }
+/// # }
+/// # impl<K, V> IntoIterator for Table<K, V> {
+/// #     type Item = (K, V);
+/// #     type IntoIter = ::std::vec::IntoIter<(K, V)>;
+/// #     fn into_iter(self) -> Self::IntoIter { self.0.into_iter() }
+/// # }
+/// # impl<'a, K, V> IntoIterator for &'a Table<K, V> {
+/// #     type Item = (&'a K, &'a V);
+/// #     type IntoIter = Box<Iterator<Item = (&'a K, &'a V)> + 'a>;
+/// #     fn into_iter(self) -> Self::IntoIter { Box::new(self.0.iter().map(|e| (&e.0, &e.1))) }
+/// # }
+/// # impl<'a, K, V> IntoIterator for &'a mut Table<K, V> {
+/// #     type Item = (&'a K, &'a mut V);
+/// #     type IntoIter = Box<Iterator<Item = (&'a K, &'a mut V)> + 'a>;
+/// #     fn into_iter(self) -> Self::IntoIter {
+/// #         Box::new(self.0.iter_mut().map(|e| (&e.0, &mut e.1)))
+/// #     }
+/// # }
+/// # impl<K, V> ::std::iter::FromIterator<(K, V)> for Table<K, V> {
+/// #     fn from_iter<I: IntoIterator<Item = (K, V)>>(i: I) -> Self {
+/// #         Table(i.into_iter().collect())
+/// #     }
+/// # }
+///
+/// impl_term_collection!([T] Stack<T>);
+/// impl_term_collection!(map [K, V] Table<K, V>);
+///
+/// fn main() {
+///     let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+///     let stack = incr.transform(Stack(vec![1, 2]));
+///     assert_eq!(stack.0, vec![2, 3]);
+///
+///     let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+///     assert_eq!(sum.query(&Table(vec![(10, 1), (20, 2)])), 3);
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_term_collection {
+    ( [ $( $params:tt )* ] $collection:ty ) => {
+        impl< $( $params )* > $crate::Term for $collection
+        where
+            $collection: $crate::Castable
+                + IntoIterator
+                + $crate::export::FromIterator<<$collection as IntoIterator>::Item>,
+            <$collection as IntoIterator>::Item: $crate::Term,
+            for<'b> &'b $collection: IntoIterator<Item = &'b <$collection as IntoIterator>::Item>,
+            for<'b> &'b mut $collection:
+                IntoIterator<Item = &'b mut <$collection as IntoIterator>::Item>,
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> Self
+            where
+                F: $crate::GenericTransform,
+            {
+                self.into_iter().map(|t| f.transform(t)).collect()
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                for t in self {
+                    let r = query.query(t);
+                    each(query, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                for t in self {
+                    let r = query.query(t);
+                    each(query, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                for t in self {
+                    let r = mutation.mutate(t);
+                    each(mutation, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                for t in self {
+                    let r = mutation.mutate(t);
+                    each(mutation, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: $crate::GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                for t in self {
+                    let r = consume.consume(t);
+                    each(consume, r);
+                }
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<Self> {
+                self.into_iter().map($crate::Term::clone_term).collect()
+            }
+        }
+    };
+
+    ( map [ $( $params:tt )* ] $map:ty ) => {
+        impl< $( $params )* > $crate::Term for $map
+        where
+            $map: $crate::Castable
+                + IntoIterator<Item = (K, V)>
+                + $crate::export::FromIterator<(K, V)>,
+            V: $crate::Term,
+            for<'b> &'b $map: IntoIterator<Item = (&'b K, &'b V)>,
+            for<'b> &'b mut $map: IntoIterator<Item = (&'b K, &'b mut V)>,
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> Self
+            where
+                F: $crate::GenericTransform,
+            {
+                self.into_iter().map(|(k, v)| (k, f.transform(v))).collect()
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                for (_, v) in self {
+                    let r = query.query(v);
+                    each(query, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                for (_, v) in self {
+                    let r = query.query(v);
+                    each(query, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                for (_, v) in self {
+                    let r = mutation.mutate(v);
+                    each(mutation, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                for (_, v) in self {
+                    let r = mutation.mutate(v);
+                    each(mutation, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: $crate::GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                for (_, v) in self {
+                    let r = consume.consume(v);
+                    each(consume, r);
+                }
+            }
+        }
+    };
+}
+
+/// Implement `Term` for a `struct` or `enum` by listing the fields to traverse,
+/// for when `#[derive(Term)]` is not an option.
+///
+/// Give the type's generic parameters in brackets, along with any bounds they
+/// need for the fields to be `Term`s, followed by the type.
+///
+/// For a `struct`, list the fields that are direct children, in order. Tuple
+/// struct fields are listed by index. Fields that are not listed are left
+/// alone, and need not be `Term`s, but the `struct` must not implement `Drop`.
+///
+/// For an `enum`, list every variant as a path, with braces around its fields,
+/// even for tuple and unit variants. Each field is given a name to bind it to,
+/// and every field is a direct child.
+///
+/// As with `#[derive(Term)]`, `Term::clone_term` clones the value whenever the
+/// given bounds are enough for the type to be `Clone`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate scrapmetal;
+///
+/// use scrapmetal::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Point<T> {
+///     x: T,
+///     y: T,
+///     label: &'static str,
+/// }
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Meters(f64);
+///
+/// #[derive(Debug, PartialEq)]
+/// pub enum Shape<T> {
+///     Circle(Point<T>, Meters),
+///     Line { from: Point<T>, to: Point<T> },
+///     Empty,
+/// }
+///
+/// impl_term!(struct [T: Term] Point<T> { x, y });
+/// impl_term!(struct [] Meters { 0 });
+/// impl_term!(enum [T: Term] Shape<T> {
+///     Shape::Circle { 0: center, 1: radius },
+///     Shape::Line { from: from, to: to },
+///     Shape::Empty {},
+/// });
+///
+/// fn main() {
+///     let mut double = Everywhere::new(Transformation::new(|f: f64| f * 2.0));
+///     let center = Point { x: 1.0, y: 2.0, label: "center" };
+///     assert_eq!(
+///         double.transform(Shape::Circle(center, Meters(3.0))),
+///         Shape::Circle(Point { x: 2.0, y: 4.0, label: "center" }, Meters(6.0))
+///     );
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_term {
+    ( struct [ $( $params:tt )* ] $name:ty { $( $field:tt ),* $(,)* } ) => {
+        impl< $( $params )* > $crate::Term for $name {
+            #[inline]
+            #[allow(unknown_lints, clippy::needless_update)]
+            fn map_one_transform<F>(self, f: &mut F) -> Self
+            where
+                F: $crate::GenericTransform,
+            {
+                Self {
+                    $( $field: f.transform(self.$field), )*
+                    ..self
+                }
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                $(
+                    let r = query.query(&self.$field);
+                    each(query, r);
+                )*
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                $(
+                    let r = query.query(&self.$field);
+                    each(query, r);
+                )*
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                $(
+                    let r = mutation.mutate(&mut self.$field);
+                    each(mutation, r);
+                )*
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                $(
+                    let r = mutation.mutate(&mut self.$field);
+                    each(mutation, r);
+                )*
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: $crate::GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                $(
+                    let r = consume.consume(self.$field);
+                    each(consume, r);
+                )*
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<Self> {
+                #[allow(unused_imports)]
+                use $crate::export::{NotTermClone, TermClone};
+                $crate::export::CloneProbe(self).clone_term()
+            }
+        }
+    };
+
+    (
+        enum [ $( $params:tt )* ] $name:ty {
+            $( $( $variant:ident )::+ { $( $field:tt : $binding:ident ),* $(,)* } ),* $(,)*
+        }
+    ) => {
+        impl< $( $params )* > $crate::Term for $name {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> Self
+            where
+                F: $crate::GenericTransform,
+            {
+                match self {
+                    $(
+                        $( $variant )::+ { $( $field: $binding ),* } => {
+                            $( $variant )::+ { $( $field: f.transform($binding) ),* }
+                        }
+                    )*
+                }
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ { $( $field: ref $binding ),* } => {
+                            $(
+                                let r = query.query($binding);
+                                each(query, r);
+                            )*
+                        }
+                    )*
+                }
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: $crate::GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ { $( $field: ref $binding ),* } => {
+                            $(
+                                let r = query.query($binding);
+                                each(query, r);
+                            )*
+                        }
+                    )*
+                }
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ { $( $field: ref mut $binding ),* } => {
+                            $(
+                                let r = mutation.mutate($binding);
+                                each(mutation, r);
+                            )*
+                        }
+                    )*
+                }
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: $crate::GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ { $( $field: ref mut $binding ),* } => {
+                            $(
+                                let r = mutation.mutate($binding);
+                                each(mutation, r);
+                            )*
+                        }
+                    )*
+                }
+            }
+
+            #[inline]
+            #[allow(unused_variables, unused_mut)]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: $crate::GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                match self {
+                    $(
+                        $( $variant )::+ { $( $field: $binding ),* } => {
+                            $(
+                                let r = consume.consume($binding);
+                                each(consume, r);
+                            )*
+                        }
+                    )*
+                }
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<Self> {
+                #[allow(unused_imports)]
+                use $crate::export::{NotTermClone, TermClone};
+                $crate::export::CloneProbe(self).clone_term()
+            }
+        }
+    };
+}
+
+/// Define `syn`-style visitor traits, with a named method for each of a set of
+/// `Term` types, for code that prefers overriding named hooks to passing
+/// type-directed closures.
+///
+/// Name the three traits to define, then list each type along with the names
+/// of its methods in each trait, in the same order. This defines:
+///
+/// * A `Visit<'ast>` trait, with a `fn visit_company(&mut self, node: &'ast
+///   Company)` method for each type, and a `visit_children` method.
+///
+/// * A `VisitMut` trait, with a `fn visit_company_mut(&mut self, node: &mut
+///   Company)` method for each type, and a `visit_children_mut` method.
+///
+/// * A `Fold` trait, with a `fn fold_company(&mut self, node: Company) ->
+///   Company` method for each type, and a `fold_children` method.
+///
+/// By default, each method recurses into the node's children with the
+/// `*_children` method, which hands each child of a listed type to its method,
+/// and recurses into the children of every other child in turn, using the
+/// node's `Term` one-layer traversals. When overriding a method, call the
+/// `*_children` method to keep recursing.
+///
+/// The `Visit` trait borrows every node for `'ast`, so it cannot see inside a
+/// `Cell`, `RefCell`, `Mutex`, or `RwLock`, and visits an
+/// `Inaccessible::Interior` in place of each one's value. List `Inaccessible`
+/// among the types to be told about them.
+///
+/// ```
+/// #[macro_use]
+/// extern crate scrapmetal;
+///
+/// use scrapmetal::*;
+///
+/// pub struct Company([Department; 2]);
+/// pub struct Department(&'static str, [Employee; 1]);
+/// pub struct Employee(&'static str, f64);
+///
+/// impl_term!(struct [] Company { 0 });
+/// impl_term!(struct [] Department { 0, 1 });
+/// impl_term!(struct [] Employee { 0, 1 });
+///
+/// define_visitors! {
+///     pub trait Visit, VisitMut, Fold;
+///
+///     Company => visit_company, visit_company_mut, fold_company;
+///     Department => visit_department, visit_department_mut, fold_department;
+///     Employee => visit_employee, visit_employee_mut, fold_employee;
+/// }
+///
+/// struct Payroll(f64);
+///
+/// impl<'ast> Visit<'ast> for Payroll {
+///     fn visit_employee(&mut self, employee: &'ast Employee) {
+///         self.0 += employee.1;
+///     }
+/// }
+///
+/// struct Raise(f64);
+///
+/// impl VisitMut for Raise {
+///     fn visit_employee_mut(&mut self, employee: &mut Employee) {
+///         employee.1 *= 1.0 + self.0;
+///     }
+/// }
+///
+/// fn main() {
+///     let mut company = Company([
+///         Department("R&D", [Employee("Ada", 100.0)]),
+///         Department("Sales", [Employee("Grace", 50.0)]),
+///     ]);
+///
+///     Raise(0.5).visit_company_mut(&mut company);
+///
+///     let mut payroll = Payroll(0.0);
+///     payroll.visit_company(&company);
+///     assert_eq!(payroll.0, 225.0);
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_visitors {
+    (
+        $vis:vis trait $visit_trait:ident, $visit_mut_trait:ident, $fold_trait:ident;
+        $( $ty:ty => $visit:ident, $visit_mut:ident, $fold:ident; )*
+    ) => {
+        /// Visits values by shared reference, with a method for each type.
+        $vis trait $visit_trait<'ast> {
+            $(
+                /// Visit a node of this type. By default, visit its children.
+                #[inline]
+                fn $visit(&mut self, node: &'ast $ty) {
+                    self.visit_children(node);
+                }
+            )*
+
+            /// Visit each of `node`'s children, handing those of the visited
+            /// types to their methods, and visiting the children of the rest.
+            fn visit_children<T>(&mut self, node: &'ast T)
+            where
+                T: $crate::Term,
+            {
+                struct Children<'ast, 'v, V: ?Sized + 'v>(
+                    &'v mut V,
+                    $crate::export::PhantomData<&'ast ()>,
+                );
+
+                impl<'ast, 'v, V> $crate::GenericQueryRef<'ast, ()> for Children<'ast, 'v, V>
+                where
+                    V: ?Sized + $visit_trait<'ast>,
+                {
+                    fn query<T>(&mut self, t: &'ast T)
+                    where
+                        T: $crate::Term,
+                    {
+                        $(
+                            let mut visit = $crate::QueryRef::or_else(
+                                || false,
+                                |node: &'ast $ty| {
+                                    self.0.$visit(node);
+                                    true
+                                },
+                            );
+                            if $crate::GenericQueryRef::query(&mut visit, t) {
+                                return;
+                            }
+                        )*
+                        $crate::Term::map_one_query_ref(t, self, |_, ()| ());
+                    }
+                }
+
+                let mut children = Children(self, $crate::export::PhantomData);
+                $crate::Term::map_one_query_ref(node, &mut children, |_, ()| ());
+            }
+        }
+
+        /// Visits values by mutable reference, with a method for each type.
+        $vis trait $visit_mut_trait {
+            $(
+                /// Visit a node of this type. By default, visit its children.
+                #[inline]
+                fn $visit_mut(&mut self, node: &mut $ty) {
+                    self.visit_children_mut(node);
+                }
+            )*
+
+            /// Visit each of `node`'s children, handing those of the visited
+            /// types to their methods, and visiting the children of the rest.
+            fn visit_children_mut<T>(&mut self, node: &mut T)
+            where
+                T: $crate::Term,
+            {
+                struct Children<'v, V: ?Sized + 'v>(&'v mut V);
+
+                impl<'v, V> $crate::GenericMutate<()> for Children<'v, V>
+                where
+                    V: ?Sized + $visit_mut_trait,
+                {
+                    fn mutate<T>(&mut self, t: &mut T)
+                    where
+                        T: $crate::Term,
+                    {
+                        $(
+                            let mut visit = $crate::Mutation::or_else(
+                                || false,
+                                |node: &mut $ty| {
+                                    self.0.$visit_mut(node);
+                                    true
+                                },
+                            );
+                            if $crate::GenericMutate::mutate(&mut visit, t) {
+                                return;
+                            }
+                        )*
+                        $crate::Term::map_one_mutation(t, self, |_, ()| ());
+                    }
+                }
+
+                $crate::Term::map_one_mutation(node, &mut Children(self), |_, ()| ());
+            }
+        }
+
+        /// Rebuilds values from their parts, with a method for each type.
+        $vis trait $fold_trait {
+            $(
+                /// Fold a node of this type. By default, fold its children.
+                #[inline]
+                fn $fold(&mut self, node: $ty) -> $ty {
+                    self.fold_children(node)
+                }
+            )*
+
+            /// Fold each of `node`'s children, handing those of the folded
+            /// types to their methods, and folding the children of the rest.
+            fn fold_children<T>(&mut self, node: T) -> T
+            where
+                T: $crate::Term,
+            {
+                struct Children<'v, V: ?Sized + 'v>(&'v mut V);
+
+                impl<'v, V> $crate::GenericTransform for Children<'v, V>
+                where
+                    V: ?Sized + $fold_trait,
+                {
+                    fn transform<T>(&mut self, t: T) -> T
+                    where
+                        T: $crate::Term,
+                    {
+                        $(
+                            let mut is_folded = $crate::Query::or_else(|| false, |_: &$ty| true);
+                            if $crate::GenericQuery::query(&mut is_folded, &t) {
+                                let mut fold = $crate::Transformation::new(|node: $ty| {
+                                    self.0.$fold(node)
+                                });
+                                return $crate::GenericTransform::transform(&mut fold, t);
+                            }
+                        )*
+                        $crate::Term::map_one_transform(t, self)
+                    }
+                }
+
+                $crate::Term::map_one_transform(node, &mut Children(self))
+            }
+        }
+    };
+}
@@ -1,5 +1,8 @@
-use super::{Cast, Term};
-use std::marker::PhantomData;
+use super::{Castable, Term};
+use cast::cast_mut;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 /// A similar work around as `GenericTransform`, but mutating in place and
 /// optionally returning some query type, rather than taking `self` and
@@ -13,6 +16,16 @@ pub trait GenericMutate<R> {
         T: Term;
 }
 
+/// Like `GenericMutate`, but the mutated value is borrowed for `'a`, so the
+/// mutation's results may borrow from it too. This is roughly equivalent to
+/// `for<T> FnMut(&'a mut T) -> R`.
+pub trait GenericMutateRef<'a, R> {
+    /// Call the mutation function on any `T`.
+    fn mutate<T>(&mut self, t: &'a mut T) -> R
+    where
+        T: Term;
+}
+
 /// A mutation creates some value `R` from mutable references to a `U`. It can
 /// be called on values of any type `T`, not just on values of type `U`, so it
 /// requires a default `R` value for when it is called on values which are not a
@@ -68,13 +81,14 @@ impl<M, U, D, R> GenericMutate<R> for Mutation<M, U, D, R>
 where
     M: FnMut(&mut U) -> R,
     D: FnMut() -> R,
+    U: Castable,
 {
     #[inline]
     fn mutate<T>(&mut self, t: &mut T) -> R
     where
         T: Term,
     {
-        match Cast::<&mut U>::cast(t) {
+        match cast_mut::<U, _>(t) {
             Ok(u) => (self.mutation)(u),
             Err(_) => (self.make_default)(),
         }
@@ -147,6 +161,30 @@ where
     }
 }
 
+/// Collect mutable references to every outermost `U` within a value. Used to
+/// implement `Term::collect_mut`.
+#[cfg(feature = "alloc")]
+pub(crate) struct CollectMut<'a, U>(pub(crate) Vec<&'a mut U>)
+where
+    U: 'a;
+
+#[cfg(feature = "alloc")]
+impl<'a, U> GenericMutateRef<'a, ()> for CollectMut<'a, U>
+where
+    U: 'a + Castable,
+{
+    #[inline]
+    fn mutate<T>(&mut self, t: &'a mut T)
+    where
+        T: Term,
+    {
+        match cast_mut::<U, _>(t) {
+            Ok(u) => self.0.push(u),
+            Err(t) => t.map_one_mutation_ref(self, |_, ()| {}),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,7 +199,19 @@ mod tests {
         assert_eq!(set_char_to_a.mutate(&mut char), 1);
         assert_eq!(char, 'a');
 
-        let mut v = vec![1, 2, 3];
+        let mut v = [1, 2, 3];
         assert_eq!(set_char_to_a.mutate(&mut v), 0);
     }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn collecting_mut() {
+        let mut v = vec![(3, 'c'), (1, 'a'), (2, 'b')];
+        {
+            let mut numbers = v.collect_mut::<i32>();
+            numbers.sort();
+            *numbers[0] += 10;
+        }
+        assert_eq!(v, vec![(3, 'c'), (11, 'a'), (2, 'b')]);
+    }
 }
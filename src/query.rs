@@ -1,5 +1,8 @@
-use super::{Cast, Term};
-use std::marker::PhantomData;
+use super::{Castable, Term};
+use cast::cast_ref;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 /// A similar work around as `GenericTransform`, but returning a query type, rather
 /// than the same type. This is roughly equivalent to `for<T> FnMut(&T) -> R`.
@@ -10,6 +13,16 @@ pub trait GenericQuery<R> {
         T: Term;
 }
 
+/// Like `GenericQuery`, but the queried value is borrowed for `'a`, so the
+/// query's results may borrow from it too. This is roughly equivalent to
+/// `for<T> FnMut(&'a T) -> R`.
+pub trait GenericQueryRef<'a, R> {
+    /// Call the query function on any `T`.
+    fn query<T>(&mut self, t: &'a T) -> R
+    where
+        T: Term;
+}
+
 /// A query non-destructively creates some value `R` from references to a
 /// `U`. It can be called on values of any type `T`, not just on values of type
 /// `U`, so it requires a default `R` value for when it is called on values
@@ -65,13 +78,82 @@ impl<Q, U, D, R> GenericQuery<R> for Query<Q, U, D, R>
 where
     Q: FnMut(&U) -> R,
     D: FnMut() -> R,
+    U: Castable,
 {
     #[inline]
     fn query<T>(&mut self, t: &T) -> R
     where
         T: Term,
     {
-        match Cast::<&U>::cast(t) {
+        match cast_ref::<U, _>(t) {
+            Ok(u) => (self.query)(u),
+            Err(_) => (self.make_default)(),
+        }
+    }
+}
+
+/// Like `Query`, but lifts a `FnMut(&'a U) -> R` into a `for<T> FnMut(&'a T) ->
+/// R`, so that the query's results may borrow from the queried value.
+#[derive(Debug)]
+pub struct QueryRef<'a, Q, U, D, R>
+where
+    Q: FnMut(&'a U) -> R,
+    D: FnMut() -> R,
+    U: 'a,
+{
+    make_default: D,
+    query: Q,
+    phantom: PhantomData<fn(&'a U) -> R>,
+}
+
+impl<'a, Q, U, R> QueryRef<'a, Q, U, fn() -> R, R>
+where
+    Q: FnMut(&'a U) -> R,
+    R: Default,
+    U: 'a,
+{
+    /// Construct a new `QueryRef`, returning `R::default()` for the cases where
+    /// we query a value whose type is not `U`.
+    #[inline]
+    pub fn new(query: Q) -> QueryRef<'a, Q, U, fn() -> R, R> {
+        QueryRef {
+            make_default: Default::default,
+            query,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q, U, D, R> QueryRef<'a, Q, U, D, R>
+where
+    Q: FnMut(&'a U) -> R,
+    D: FnMut() -> R,
+    U: 'a,
+{
+    /// Construct a new `QueryRef`, returning `make_default()` for the cases
+    /// where we query a value whose type is not `U`.
+    #[inline]
+    pub fn or_else(make_default: D, query: Q) -> QueryRef<'a, Q, U, D, R> {
+        QueryRef {
+            make_default,
+            query,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q, U, D, R> GenericQueryRef<'a, R> for QueryRef<'a, Q, U, D, R>
+where
+    Q: FnMut(&'a U) -> R,
+    D: FnMut() -> R,
+    U: 'a + Castable,
+{
+    #[inline]
+    fn query<T>(&mut self, t: &'a T) -> R
+    where
+        T: Term,
+    {
+        match cast_ref::<U, _>(t) {
             Ok(u) => (self.query)(u),
             Err(_) => (self.make_default)(),
         }
@@ -127,6 +209,193 @@ where
     }
 }
 
+/// Like `Everything`, but recursively performs a `Q: GenericQueryRef<'a, R>`
+/// query, so that the results may borrow from the queried data structure.
+///
+/// The values inside cells and locks cannot be borrowed, so they are not
+/// queried. An `Inaccessible::Interior` is queried in place of each.
+#[derive(Debug)]
+pub struct EverythingRef<'a, Q, R, F>
+where
+    Q: GenericQueryRef<'a, R>,
+    F: FnMut(R, R) -> R,
+{
+    q: Q,
+    fold: F,
+    phantom: PhantomData<fn(&'a (), R, R) -> R>,
+}
+
+impl<'a, Q, R, F> EverythingRef<'a, Q, R, F>
+where
+    Q: GenericQueryRef<'a, R>,
+    F: FnMut(R, R) -> R,
+{
+    /// Construct a new `EverythingRef` query traversal.
+    #[inline]
+    pub fn new(q: Q, fold: F) -> EverythingRef<'a, Q, R, F> {
+        EverythingRef {
+            q,
+            fold,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q, R, F> GenericQueryRef<'a, R> for EverythingRef<'a, Q, R, F>
+where
+    Q: GenericQueryRef<'a, R>,
+    F: FnMut(R, R) -> R,
+{
+    #[inline]
+    fn query<T>(&mut self, t: &'a T) -> R
+    where
+        T: Term,
+    {
+        let mut r = Some(self.q.query(t));
+        t.map_one_query_ref(self, |me, rr| {
+            r = Some((me.fold)(r.take().unwrap(), rr));
+        });
+        r.unwrap()
+    }
+}
+
+/// Recursively fold a data structure from the bottom up, giving every `U` in
+/// it the chance to see the combined results of its own subtree.
+///
+/// Every value's result is its `Q: GenericQuery<R>` query result, joined with
+/// the results of each of its direct children by `F: FnMut(R, R) -> R`, just
+/// like `Everything`. Additionally, when the value is a `U`, that combined
+/// result is handed to `G: FnMut(&U, R) -> R` along with the `U` itself, and
+/// whatever `G` returns becomes the `U`'s result instead.
+///
+/// This is a catamorphism: each `U` sees its descendants only through their
+/// results. See `ParaUp` for a variant that also receives each child's result
+/// separately.
+#[derive(Debug)]
+pub struct FoldUp<Q, G, U, R, F>
+where
+    Q: GenericQuery<R>,
+    G: FnMut(&U, R) -> R,
+    F: FnMut(R, R) -> R,
+{
+    q: Q,
+    g: G,
+    fold: F,
+    phantom: PhantomData<fn(&U, R) -> R>,
+}
+
+impl<Q, G, U, R, F> FoldUp<Q, G, U, R, F>
+where
+    Q: GenericQuery<R>,
+    G: FnMut(&U, R) -> R,
+    F: FnMut(R, R) -> R,
+{
+    /// Construct a new `FoldUp` query traversal.
+    #[inline]
+    pub fn new(q: Q, g: G, fold: F) -> FoldUp<Q, G, U, R, F> {
+        FoldUp {
+            q,
+            g,
+            fold,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Q, G, U, R, F> GenericQuery<R> for FoldUp<Q, G, U, R, F>
+where
+    Q: GenericQuery<R>,
+    G: FnMut(&U, R) -> R,
+    F: FnMut(R, R) -> R,
+    U: Castable,
+{
+    #[inline]
+    fn query<T>(&mut self, t: &T) -> R
+    where
+        T: Term,
+    {
+        let mut r = Some(self.q.query(t));
+        t.map_one_query(self, |me, rr| {
+            r = Some((me.fold)(r.take().unwrap(), rr));
+        });
+        let r = r.unwrap();
+        match cast_ref::<U, _>(t) {
+            Ok(u) => (self.g)(u, r),
+            Err(_) => r,
+        }
+    }
+}
+
+/// Like `FoldUp`, but each `U` receives the individual results of its direct
+/// children, in traversal order, rather than their combination.
+///
+/// This is a paramorphism: the `G: FnMut(&U, Vec<R>) -> R` callback gets the
+/// original `U`, and therefore its original children, alongside the result
+/// computed for each of those children. The `Q: GenericQuery<R>` query is not
+/// called on `U` values, since `G` alone determines their results.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct ParaUp<Q, G, U, R, F>
+where
+    Q: GenericQuery<R>,
+    G: FnMut(&U, Vec<R>) -> R,
+    F: FnMut(R, R) -> R,
+{
+    q: Q,
+    g: G,
+    fold: F,
+    phantom: PhantomData<fn(&U, Vec<R>) -> R>,
+}
+
+#[cfg(feature = "alloc")]
+impl<Q, G, U, R, F> ParaUp<Q, G, U, R, F>
+where
+    Q: GenericQuery<R>,
+    G: FnMut(&U, Vec<R>) -> R,
+    F: FnMut(R, R) -> R,
+{
+    /// Construct a new `ParaUp` query traversal.
+    #[inline]
+    pub fn new(q: Q, g: G, fold: F) -> ParaUp<Q, G, U, R, F> {
+        ParaUp {
+            q,
+            g,
+            fold,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<Q, G, U, R, F> GenericQuery<R> for ParaUp<Q, G, U, R, F>
+where
+    Q: GenericQuery<R>,
+    G: FnMut(&U, Vec<R>) -> R,
+    F: FnMut(R, R) -> R,
+    U: Castable,
+{
+    #[inline]
+    fn query<T>(&mut self, t: &T) -> R
+    where
+        T: Term,
+    {
+        match cast_ref::<U, _>(t) {
+            Ok(u) => {
+                let mut children = vec![];
+                t.map_one_query(self, |_, r| children.push(r));
+                (self.g)(u, children)
+            }
+            Err(t) => {
+                let mut r = Some(self.q.query(t));
+                t.map_one_query(self, |me, rr| {
+                    r = Some((me.fold)(r.take().unwrap(), rr));
+                });
+                r.unwrap()
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,6 +405,44 @@ mod tests {
         let mut char_to_u32 = Query::or_else(|| 42, |c: &char| *c as u32);
         assert_eq!(char_to_u32.query(&'a'), 97);
         assert_eq!(char_to_u32.query(&'b'), 98);
-        assert_eq!(char_to_u32.query(&vec![1, 2, 3]), 42);
+        assert_eq!(char_to_u32.query(&[1, 2, 3]), 42);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn querying_by_ref() {
+        let v = vec![(1, "one"), (2, "two")];
+        let name = QueryRef::new(|pair: &(i32, &'static str)| vec![&pair.1]);
+        let mut names = EverythingRef::new(name, |mut a, b| {
+            a.extend(b);
+            a
+        });
+        assert_eq!(names.query(&v), vec![&"one", &"two"]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn folding_up() {
+        let count = Query::new(|_: &u32| 1);
+        let mut sizes = vec![];
+        {
+            let mut fold = FoldUp::new(
+                count,
+                |v: &Vec<u32>, n: usize| {
+                    sizes.push((v.len(), n));
+                    n
+                },
+                |a, b| a + b,
+            );
+            assert_eq!(fold.query(&vec![vec![1u32, 2], vec![], vec![3]]), 3);
+        }
+        assert_eq!(sizes, vec![(2, 2), (0, 0), (1, 1)]);
+
+        let mut para = ParaUp::new(
+            Query::new(|x: &u32| *x),
+            |_: &(u32, u32), children: Vec<u32>| children[1] * 10 + children[0],
+            |a, b| a + b,
+        );
+        assert_eq!(para.query(&vec![(1u32, 2u32), (3, 4)]), 21 + 43);
     }
 }
@@ -0,0 +1,426 @@
+//! Traversing types from other crates, which cannot implement `Term` here.
+//!
+//! `#[derive(Term)]` with `#[term(remote = "other::Type")]` on a local mirror
+//! of a remote type implements `RemoteTerm` for the mirror, and `Remote` pairs
+//! a value of the remote type with its mirror to make a `Term`.
+
+use super::{Castable, GenericConsume, GenericMutate, GenericMutateRef, GenericQuery,
+            GenericQueryRef, GenericTransform, Term};
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+/// The one-layer traversals of a type from another crate, implemented by a
+/// local mirror of that type.
+///
+/// Each function is the `Term` method of the same name, taking the remote
+/// value in place of `self`.
+pub trait RemoteTerm {
+    /// The remote type that this type mirrors.
+    type Remote;
+
+    /// Like `Term::map_one_transform`.
+    fn map_one_transform<F>(this: Self::Remote, f: &mut F) -> Self::Remote
+    where
+        F: GenericTransform;
+
+    /// Like `Term::map_one_query`.
+    fn map_one_query<Q, R, F>(this: &Self::Remote, query: &mut Q, each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R);
+
+    /// Like `Term::map_one_query_ref`.
+    fn map_one_query_ref<'a, Q, R, F>(this: &'a Self::Remote, query: &mut Q, each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R);
+
+    /// Like `Term::map_one_mutation`.
+    fn map_one_mutation<M, R, F>(this: &mut Self::Remote, mutation: &mut M, each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R);
+
+    /// Like `Term::map_one_mutation_ref`.
+    fn map_one_mutation_ref<'a, M, R, F>(this: &'a mut Self::Remote, mutation: &mut M, each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R);
+
+    /// Like `Term::map_one_consume`.
+    fn map_one_consume<C, R, F>(this: Self::Remote, consume: &mut C, each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R);
+}
+
+/// A value of a type from another crate, traversed as described by its local
+/// mirror `D`.
+///
+/// `Remote<T, D>` is a `Term` whenever `D` mirrors `T`, so transformations and
+/// queries see each remote value as a `Remote<T, D>`. Mirrors visit the remote
+/// values in their fields this way with `#[term(mirror = "...")]`, which is
+/// what makes recursive remote types traversable.
+#[repr(transparent)]
+pub struct Remote<T, D>(pub T, PhantomData<fn() -> D>);
+
+impl<T, D> Remote<T, D> {
+    /// Wrap a remote value.
+    #[inline]
+    pub fn new(t: T) -> Remote<T, D> {
+        Remote(t, PhantomData)
+    }
+
+    /// Unwrap the remote value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// View a reference to a remote value as a reference to the wrapped value.
+    #[inline]
+    pub fn from_ref(t: &T) -> &Remote<T, D> {
+        // Safe because `Remote<T, D>` is `repr(transparent)` over `T`.
+        unsafe { &*(t as *const T as *const Remote<T, D>) }
+    }
+
+    /// View a mutable reference to a remote value as a mutable reference to the
+    /// wrapped value.
+    #[inline]
+    pub fn from_mut(t: &mut T) -> &mut Remote<T, D> {
+        // Safe because `Remote<T, D>` is `repr(transparent)` over `T`.
+        unsafe { &mut *(t as *mut T as *mut Remote<T, D>) }
+    }
+}
+
+impl<T, D> From<T> for Remote<T, D> {
+    #[inline]
+    fn from(t: T) -> Remote<T, D> {
+        Remote::new(t)
+    }
+}
+
+impl<T, D> Deref for Remote<T, D> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, D> DerefMut for Remote<T, D> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Clone, D> Clone for Remote<T, D> {
+    #[inline]
+    fn clone(&self) -> Remote<T, D> {
+        Remote::new(self.0.clone())
+    }
+}
+
+impl<T: Copy, D> Copy for Remote<T, D> {}
+
+impl<T: fmt::Debug, D> fmt::Debug for Remote<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Remote").field(&self.0).finish()
+    }
+}
+
+impl<T: PartialEq, D> PartialEq for Remote<T, D> {
+    #[inline]
+    fn eq(&self, rhs: &Remote<T, D>) -> bool {
+        self.0 == rhs.0
+    }
+}
+
+impl<T: Eq, D> Eq for Remote<T, D> {}
+
+impl<T: Hash, D> Hash for Remote<T, D> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<T, D> Term for Remote<T, D>
+where
+    T: Castable,
+    D: Castable + RemoteTerm<Remote = T>,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Remote<T, D>
+    where
+        F: GenericTransform,
+    {
+        Remote::new(D::map_one_transform(self.0, f))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        D::map_one_query(&self.0, query, each)
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        D::map_one_query_ref(&self.0, query, each)
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        D::map_one_mutation(&mut self.0, mutation, each)
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        D::map_one_mutation_ref(&mut self.0, mutation, each)
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        D::map_one_consume(self.0, consume, each)
+    }
+}
+
+/// A field type that holds values of a remote type `T`, such as `T` itself,
+/// `Box<T>`, `Option<T>`, `Option<Box<T>>`, or `Vec<T>`.
+///
+/// A field with `#[term(mirror = "...")]` is traversed by visiting each of the
+/// `T`s it holds as a `Remote`, rather than visiting the field itself.
+/// Implement this for other containers to hold remote values in them.
+pub trait RemoteField<T>: Sized {
+    /// Replace each `T` with the result of calling `f` on it.
+    fn map_each<F>(self, f: F) -> Self
+    where
+        F: FnMut(T) -> T;
+
+    /// Call `f` on a reference to each `T`.
+    fn for_each_ref<'a, F>(&'a self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a T);
+
+    /// Call `f` on a mutable reference to each `T`.
+    fn for_each_mut<'a, F>(&'a mut self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a mut T);
+
+    /// Call `f` on each `T`, by value.
+    fn into_each<F>(self, f: F)
+    where
+        F: FnMut(T);
+}
+
+impl<T> RemoteField<T> for T {
+    #[inline]
+    fn map_each<F>(self, mut f: F) -> T
+    where
+        F: FnMut(T) -> T,
+    {
+        f(self)
+    }
+
+    #[inline]
+    fn for_each_ref<'a, F>(&'a self, mut f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a T),
+    {
+        f(self)
+    }
+
+    #[inline]
+    fn for_each_mut<'a, F>(&'a mut self, mut f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a mut T),
+    {
+        f(self)
+    }
+
+    #[inline]
+    fn into_each<F>(self, mut f: F)
+    where
+        F: FnMut(T),
+    {
+        f(self)
+    }
+}
+
+impl<T> RemoteField<T> for Option<T> {
+    #[inline]
+    fn map_each<F>(self, f: F) -> Option<T>
+    where
+        F: FnMut(T) -> T,
+    {
+        self.map(f)
+    }
+
+    #[inline]
+    fn for_each_ref<'a, F>(&'a self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a T),
+    {
+        self.iter().for_each(f)
+    }
+
+    #[inline]
+    fn for_each_mut<'a, F>(&'a mut self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a mut T),
+    {
+        self.iter_mut().for_each(f)
+    }
+
+    #[inline]
+    fn into_each<F>(self, f: F)
+    where
+        F: FnMut(T),
+    {
+        self.into_iter().for_each(f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> RemoteField<T> for Box<T> {
+    #[inline]
+    fn map_each<F>(self, mut f: F) -> Box<T>
+    where
+        F: FnMut(T) -> T,
+    {
+        Box::new(f(*self))
+    }
+
+    #[inline]
+    fn for_each_ref<'a, F>(&'a self, mut f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a T),
+    {
+        f(self)
+    }
+
+    #[inline]
+    fn for_each_mut<'a, F>(&'a mut self, mut f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a mut T),
+    {
+        f(self)
+    }
+
+    #[inline]
+    fn into_each<F>(self, mut f: F)
+    where
+        F: FnMut(T),
+    {
+        f(*self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> RemoteField<T> for Vec<T> {
+    #[inline]
+    fn map_each<F>(self, f: F) -> Vec<T>
+    where
+        F: FnMut(T) -> T,
+    {
+        self.into_iter().map(f).collect()
+    }
+
+    #[inline]
+    fn for_each_ref<'a, F>(&'a self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a T),
+    {
+        self.iter().for_each(f)
+    }
+
+    #[inline]
+    fn for_each_mut<'a, F>(&'a mut self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a mut T),
+    {
+        self.iter_mut().for_each(f)
+    }
+
+    #[inline]
+    fn into_each<F>(self, f: F)
+    where
+        F: FnMut(T),
+    {
+        self.into_iter().for_each(f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> RemoteField<T> for Option<Box<T>> {
+    #[inline]
+    fn map_each<F>(self, mut f: F) -> Option<Box<T>>
+    where
+        F: FnMut(T) -> T,
+    {
+        self.map(|t| Box::new(f(*t)))
+    }
+
+    #[inline]
+    fn for_each_ref<'a, F>(&'a self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a T),
+    {
+        self.as_deref().into_iter().for_each(f)
+    }
+
+    #[inline]
+    fn for_each_mut<'a, F>(&'a mut self, f: F)
+    where
+        T: 'a,
+        F: FnMut(&'a mut T),
+    {
+        self.as_deref_mut().into_iter().for_each(f)
+    }
+
+    #[inline]
+    fn into_each<F>(self, f: F)
+    where
+        F: FnMut(T),
+    {
+        self.map(|t| *t).into_iter().for_each(f)
+    }
+}
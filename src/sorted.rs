@@ -0,0 +1,22 @@
+/// A `HashMap` or `HashSet` that is traversed in sorted order.
+///
+/// Hash containers are otherwise traversed in whatever order they iterate in,
+/// which, with the default `RandomState` hasher, differs from one run of the
+/// program to the next. That makes folds like "the first `U` found" unstable.
+/// Wrapping a container in `Sorted` visits a map's values in the order of
+/// their keys, and a set's elements in their own order, at the cost of sorting
+/// on every traversal.
+///
+/// Otherwise, a `Sorted` container is traversed just like the container itself:
+/// a map's keys are fixed, and transforming or mutating a set's elements
+/// rebuilds the set, merging elements that become equal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sorted<C>(pub C);
+
+impl<C> Sorted<C> {
+    /// Unwrap the container.
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
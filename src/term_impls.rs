@@ -1,37 +1,148 @@
-use super::{GenericMutate, GenericQuery, GenericTransform, Term};
-use std::collections::*;
-use std::iter::FromIterator;
+#[cfg(feature = "alloc")]
+use super::Keyed;
+use super::{Castable, GenericConsume, GenericMutate, GenericMutateRef, GenericQuery,
+            GenericQueryRef, GenericTransform, Inaccessible, Leaf, Opaque, Term};
+#[cfg(feature = "std")]
+use super::Sorted;
+use field::{CloneProbe, NotTermClone, TermClone};
+#[cfg(feature = "alloc")]
+use alloc::borrow::{Cow, ToOwned};
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+#[cfg(feature = "alloc")]
+use alloc::rc::Rc;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::ffi::{CString, IntoStringError, NulError};
+#[cfg(feature = "alloc")]
+use alloc::string::{FromUtf16Error, FromUtf8Error, String};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::cell::{BorrowError, BorrowMutError, Cell, RefCell};
+use core::char::{CharTryFromError, DecodeUtf16Error};
+use core::cmp::{self, Reverse};
+use core::ffi::{CStr, FromBytesWithNulError};
+use core::fmt;
+#[cfg(feature = "std")]
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use core::mem;
+use core::mem::{Discriminant, ManuallyDrop};
+use core::net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4,
+                SocketAddrV6};
+use core::num::{FpCategory, NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8,
+                NonZeroIsize, NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8,
+                NonZeroUsize, ParseFloatError, ParseIntError, Saturating, TryFromIntError,
+                Wrapping};
+use core::ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo,
+                RangeToInclusive};
+#[cfg(feature = "alloc")]
+use core::ptr;
+use core::str::{ParseBoolError, Utf8Error};
+use core::sync::atomic;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::env::{JoinPathsError, VarError};
+#[cfg(feature = "std")]
+use std::ffi::{OsStr, OsString};
+#[cfg(feature = "std")]
+use std::io::{ErrorKind, SeekFrom};
+#[cfg(feature = "std")]
+use std::net::Shutdown;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf, StripPrefixError};
+#[cfg(feature = "std")]
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError,
+                      TrySendError};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError, RwLock, TryLockError};
+#[cfg(feature = "std")]
+use std::thread::ThreadId;
+#[cfg(feature = "std")]
+use std::time::{Instant, SystemTime, SystemTimeError};
 
 macro_rules! impl_trivial_term {
-    ( $name:ty ) => {
-        impl Term for $name {
-            #[inline]
-            fn map_one_transform<F>(self, _: &mut F) -> Self
-            where
-                F: GenericTransform,
-            {
-                self
-            }
+    ( @methods ) => {
+        #[inline]
+        fn map_one_transform<F>(self, _: &mut F) -> Self
+        where
+            F: GenericTransform,
+        {
+            self
+        }
 
-            #[inline]
-            fn map_one_query<Q, R, F>(&self, _: &mut Q, _: F)
-            where
-                Q: GenericQuery<R>,
-                F: FnMut(&mut Q, R),
-            {}
+        #[inline]
+        fn map_one_query<Q, R, F>(&self, _: &mut Q, _: F)
+        where
+            Q: GenericQuery<R>,
+            F: FnMut(&mut Q, R),
+        {}
 
-            #[inline]
-            fn map_one_mutation<M, R, F>(&mut self, _: &mut M, _: F)
-            where
-                M: GenericMutate<R>,
-                F: FnMut(&mut M, R),
-            {}
+        #[inline]
+        fn map_one_query_ref<'a, Q, R, F>(&'a self, _: &mut Q, _: F)
+        where
+            Q: GenericQueryRef<'a, R>,
+            F: FnMut(&mut Q, R),
+        {}
+
+        #[inline]
+        fn map_one_mutation<M, R, F>(&mut self, _: &mut M, _: F)
+        where
+            M: GenericMutate<R>,
+            F: FnMut(&mut M, R),
+        {}
+
+        #[inline]
+        fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+        where
+            M: GenericMutateRef<'a, R>,
+            F: FnMut(&mut M, R),
+        {}
+
+        #[inline]
+        fn map_one_consume<C, R, F>(self, _: &mut C, _: F)
+        where
+            C: GenericConsume<R>,
+            F: FnMut(&mut C, R),
+        {}
+
+        #[inline]
+        fn clone_term(&self) -> Option<Self> {
+            CloneProbe(self).clone_term()
         }
-    }
+    };
+    ( [ $( $params:ident ),* ] $name:ty ) => {
+        impl< $( $params ),* > Term for $name
+        where
+            $( $params: Castable ),*
+        {
+            impl_trivial_term!(@methods);
+        }
+    };
+    ( < $lifetime:lifetime > $name:ty ) => {
+        impl<$lifetime> Term for $name
+        where
+            $name: Castable,
+        {
+            impl_trivial_term!(@methods);
+        }
+    };
+    ( $name:ty ) => {
+        impl_trivial_term!([] $name);
+    };
 }
 
 impl_trivial_term!(());
-impl_trivial_term!(&'static str);
+// Borrowed leaves of any lifetime are only `Castable`, and so only `Term`s,
+// with the `nightly` feature. Otherwise, they must be `'static`.
+impl_trivial_term!(<'b> &'b str);
 impl_trivial_term!(bool);
 impl_trivial_term!(char);
 impl_trivial_term!(f32);
@@ -46,6 +157,96 @@ impl_trivial_term!(i8);
 impl_trivial_term!(i16);
 impl_trivial_term!(i32);
 impl_trivial_term!(i64);
+impl_trivial_term!(u128);
+impl_trivial_term!(i128);
+impl_trivial_term!(NonZeroUsize);
+impl_trivial_term!(NonZeroU8);
+impl_trivial_term!(NonZeroU16);
+impl_trivial_term!(NonZeroU32);
+impl_trivial_term!(NonZeroU64);
+impl_trivial_term!(NonZeroU128);
+impl_trivial_term!(NonZeroIsize);
+impl_trivial_term!(NonZeroI8);
+impl_trivial_term!(NonZeroI16);
+impl_trivial_term!(NonZeroI32);
+impl_trivial_term!(NonZeroI64);
+impl_trivial_term!(NonZeroI128);
+impl_trivial_term!(Duration);
+impl_trivial_term!(TypeId);
+impl_trivial_term!(RangeFull);
+impl_trivial_term!(Ipv4Addr);
+impl_trivial_term!(Ipv6Addr);
+impl_trivial_term!(IpAddr);
+impl_trivial_term!(SocketAddrV4);
+impl_trivial_term!(SocketAddrV6);
+impl_trivial_term!(SocketAddr);
+impl_trivial_term!(fmt::Error);
+impl_trivial_term!(ParseIntError);
+impl_trivial_term!(ParseFloatError);
+impl_trivial_term!(TryFromIntError);
+impl_trivial_term!(ParseBoolError);
+impl_trivial_term!(Utf8Error);
+impl_trivial_term!(CharTryFromError);
+impl_trivial_term!(DecodeUtf16Error);
+impl_trivial_term!(AddrParseError);
+impl_trivial_term!(BorrowError);
+impl_trivial_term!(BorrowMutError);
+impl_trivial_term!(FromBytesWithNulError);
+impl_trivial_term!([T] PhantomData<T>);
+impl_trivial_term!([T] Discriminant<T>);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(FromUtf8Error);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(FromUtf16Error);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(NulError);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(IntoStringError);
+#[cfg(feature = "std")]
+impl_trivial_term!(Instant);
+#[cfg(feature = "std")]
+impl_trivial_term!(SystemTime);
+#[cfg(feature = "std")]
+impl_trivial_term!(SystemTimeError);
+#[cfg(feature = "std")]
+impl_trivial_term!(ThreadId);
+#[cfg(feature = "std")]
+impl_trivial_term!(VarError);
+#[cfg(feature = "std")]
+impl_trivial_term!(JoinPathsError);
+#[cfg(feature = "std")]
+impl_trivial_term!(StripPrefixError);
+#[cfg(feature = "std")]
+impl_trivial_term!(RecvError);
+#[cfg(feature = "std")]
+impl_trivial_term!(RecvTimeoutError);
+#[cfg(feature = "std")]
+impl_trivial_term!(TryRecvError);
+impl_trivial_term!(cmp::Ordering);
+impl_trivial_term!(FpCategory);
+impl_trivial_term!(atomic::Ordering);
+impl_trivial_term!(<'b> &'b CStr);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(String);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(Box<str>);
+#[cfg(feature = "alloc")]
+impl_trivial_term!(CString);
+#[cfg(feature = "std")]
+impl_trivial_term!(OsString);
+#[cfg(feature = "std")]
+impl_trivial_term!(<'b> &'b OsStr);
+#[cfg(feature = "std")]
+impl_trivial_term!(PathBuf);
+#[cfg(feature = "std")]
+impl_trivial_term!(<'b> &'b Path);
+#[cfg(feature = "std")]
+impl_trivial_term!(ErrorKind);
+#[cfg(feature = "std")]
+impl_trivial_term!(Shutdown);
+impl_trivial_term!(Inaccessible);
+impl_trivial_term!([T] Leaf<T>);
+impl_trivial_term!([T] Opaque<T>);
 
 macro_rules! impl_tuple_term {
     ( $name:ident $( , $names:ident )* ) => {
@@ -79,6 +280,22 @@ macro_rules! impl_tuple_term {
                 )*
             }
 
+            #[inline]
+            #[allow(non_snake_case)]
+            fn map_one_query_ref<'a, Q, R, FF>(&'a self, q: &mut Q, mut each: FF)
+            where
+                Q: GenericQueryRef<'a, R>,
+                FF: FnMut(&mut Q, R),
+            {
+                let ( ref $name $( , ref $names )* ) = *self;
+                let r = q.query( $name );
+                each(q, r);
+                $(
+                    let r = q.query( $names );
+                    each(q, r);
+                )*
+            }
+
             #[inline]
             #[allow(non_snake_case)]
             fn map_one_mutation<M, R, FF>(&mut self, m: &mut M, mut each: FF)
@@ -94,6 +311,45 @@ macro_rules! impl_tuple_term {
                     each(m, r);
                 )*
             }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn map_one_mutation_ref<'a, M, R, FF>(&'a mut self, m: &mut M, mut each: FF)
+            where
+                M: GenericMutateRef<'a, R>,
+                FF: FnMut(&mut M, R),
+            {
+                let ( ref mut $name $( , ref mut $names )* ) = *self;
+                let r = m.mutate( $name );
+                each(m, r);
+                $(
+                    let r = m.mutate( $names );
+                    each(m, r);
+                )*
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn map_one_consume<CC, R, FF>(self, c: &mut CC, mut each: FF)
+            where
+                CC: GenericConsume<R>,
+                FF: FnMut(&mut CC, R),
+            {
+                let ( $name $( , $names )* ) = self;
+                let r = c.consume( $name );
+                each(c, r);
+                $(
+                    let r = c.consume( $names );
+                    each(c, r);
+                )*
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn clone_term(&self) -> Option<Self> {
+                let ( ref $name $( , ref $names )* ) = *self;
+                Some(( Term::clone_term($name)? $( , Term::clone_term($names)? )* ))
+            }
         }
     }
 }
@@ -110,16 +366,252 @@ impl_tuple_term!(A, B, C, D, E, F, G, H, I, J);
 impl_tuple_term!(A, B, C, D, E, F, G, H, I, J, K);
 impl_tuple_term!(A, B, C, D, E, F, G, H, I, J, K, L);
 
-impl<T> Term for Vec<T>
+macro_rules! impl_enum_term {
+    (
+        [ $( $params:ident ),* ] $name:ty
+        { $( $( $variant:ident )::+ ),* }
+        { $( $( $unit:ident )::+ ),* }
+    ) => {
+        impl< $( $params ),* > Term for $name
+        where
+            $( $params: Term ),*
+        {
+            #[inline]
+            #[allow(unreachable_patterns)]
+            fn map_one_transform<F>(self, f: &mut F) -> Self
+            where
+                F: GenericTransform,
+            {
+                match self {
+                    $( $( $variant )::+ (t) => $( $variant )::+ (f.transform(t)), )*
+                    other => other,
+                }
+            }
+
+            #[inline]
+            #[allow(unreachable_patterns)]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ (ref t) => {
+                            let r = query.query(t);
+                            each(query, r);
+                        }
+                    )*
+                    _ => {}
+                }
+            }
+
+            #[inline]
+            #[allow(unreachable_patterns)]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ (ref t) => {
+                            let r = query.query(t);
+                            each(query, r);
+                        }
+                    )*
+                    _ => {}
+                }
+            }
+
+            #[inline]
+            #[allow(unreachable_patterns)]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ (ref mut t) => {
+                            let r = mutation.mutate(t);
+                            each(mutation, r);
+                        }
+                    )*
+                    _ => {}
+                }
+            }
+
+            #[inline]
+            #[allow(unreachable_patterns)]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                match *self {
+                    $(
+                        $( $variant )::+ (ref mut t) => {
+                            let r = mutation.mutate(t);
+                            each(mutation, r);
+                        }
+                    )*
+                    _ => {}
+                }
+            }
+
+            #[inline]
+            #[allow(unreachable_patterns)]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                match self {
+                    $(
+                        $( $variant )::+ (t) => {
+                            let r = consume.consume(t);
+                            each(consume, r);
+                        }
+                    )*
+                    _ => {}
+                }
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<Self> {
+                match *self {
+                    $( $( $variant )::+ (ref t) => Term::clone_term(t).map($( $variant )::+), )*
+                    $( $( $unit )::+ => Some($( $unit )::+), )*
+                }
+            }
+        }
+    }
+}
+
+impl_enum_term!([T] Option<T> { Option::Some } { Option::None });
+impl_enum_term!([T, E] Result<T, E> { Result::Ok, Result::Err } {});
+impl_enum_term!([T] Bound<T> { Bound::Included, Bound::Excluded } { Bound::Unbounded });
+impl_enum_term!([T] Reverse<T> { Reverse } {});
+impl_enum_term!([T] Wrapping<T> { Wrapping } {});
+impl_enum_term!([T] Saturating<T> { Saturating } {});
+#[cfg(feature = "std")]
+impl_enum_term!([] SeekFrom { SeekFrom::Start, SeekFrom::End, SeekFrom::Current } {});
+#[cfg(feature = "std")]
+impl_enum_term!([T] TryLockError<T> { TryLockError::Poisoned } { TryLockError::WouldBlock });
+#[cfg(feature = "std")]
+impl_enum_term!([T] SendError<T> { SendError } {});
+#[cfg(feature = "std")]
+impl_enum_term!([T] TrySendError<T> { TrySendError::Full, TrySendError::Disconnected } {});
+
+macro_rules! impl_struct_term {
+    ( [ $( $params:ident ),* ] $name:ident { $( $field:ident ),* } ) => {
+        impl< $( $params ),* > Term for $name< $( $params ),* >
+        where
+            $( $params: Term ),*
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> Self
+            where
+                F: GenericTransform,
+            {
+                $name {
+                    $( $field: f.transform(self.$field), )*
+                }
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                $(
+                    let r = query.query(&self.$field);
+                    each(query, r);
+                )*
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                $(
+                    let r = query.query(&self.$field);
+                    each(query, r);
+                )*
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                $(
+                    let r = mutation.mutate(&mut self.$field);
+                    each(mutation, r);
+                )*
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                $(
+                    let r = mutation.mutate(&mut self.$field);
+                    each(mutation, r);
+                )*
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                $(
+                    let r = consume.consume(self.$field);
+                    each(consume, r);
+                )*
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<Self> {
+                Some($name {
+                    $( $field: Term::clone_term(&self.$field)?, )*
+                })
+            }
+        }
+    }
+}
+
+impl_struct_term!([T] Range { start, end });
+impl_struct_term!([T] RangeFrom { start });
+impl_struct_term!([T] RangeTo { end });
+impl_struct_term!([T] RangeToInclusive { end });
+
+/// `RangeInclusive` gives no mutable access to its bounds, so mutating one
+/// rebuilds it from clones of its bounds, resetting it if it was exhausted by
+/// iteration. If either bound cannot be cloned, mutations visit nothing, and
+/// `map_one_mutation_ref`, whose results would borrow from the clones, never
+/// visits anything.
+impl<T> Term for RangeInclusive<T>
 where
     T: Term,
 {
     #[inline]
-    fn map_one_transform<F>(mut self, f: &mut F) -> Vec<T>
+    fn map_one_transform<F>(self, f: &mut F) -> RangeInclusive<T>
     where
         F: GenericTransform,
     {
-        self.drain(..).map(|t| f.transform(t)).collect()
+        let (start, end) = self.into_inner();
+        let start = f.transform(start);
+        RangeInclusive::new(start, f.transform(end))
     }
 
     #[inline]
@@ -128,12 +620,22 @@ where
         Q: GenericQuery<R>,
         F: FnMut(&mut Q, R),
     {
-        self.iter()
-            .map(|t| {
-                let r = query.query(t);
-                each(query, r);
-            })
-            .count();
+        let r = query.query(self.start());
+        each(query, r);
+        let r = query.query(self.end());
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(self.start());
+        each(query, r);
+        let r = query.query(self.end());
+        each(query, r);
     }
 
     #[inline]
@@ -142,25 +644,55 @@ where
         M: GenericMutate<R>,
         F: FnMut(&mut M, R),
     {
-        self.iter_mut()
-            .map(|t| {
-                let r = mutation.mutate(t);
-                each(mutation, r);
-            })
-            .count();
+        if let Some(range) = self.clone_term() {
+            let (mut start, mut end) = range.into_inner();
+            let r = mutation.mutate(&mut start);
+            each(mutation, r);
+            let r = mutation.mutate(&mut end);
+            each(mutation, r);
+            *self = RangeInclusive::new(start, end);
+        }
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {}
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let (start, end) = self.into_inner();
+        let r = consume.consume(start);
+        each(consume, r);
+        let r = consume.consume(end);
+        each(consume, r);
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<RangeInclusive<T>> {
+        let start = self.start().clone_term()?;
+        Some(RangeInclusive::new(start, self.end().clone_term()?))
     }
 }
 
-impl<T> Term for Box<T>
+/// Traversals see through a `ManuallyDrop` to its value, which is still never
+/// dropped, unless a transformation or consumption drops it.
+impl<T> Term for ManuallyDrop<T>
 where
-    T: Sized + Term,
+    T: Term,
 {
     #[inline]
-    fn map_one_transform<F>(self, f: &mut F) -> Box<T>
+    fn map_one_transform<F>(self, f: &mut F) -> ManuallyDrop<T>
     where
         F: GenericTransform,
     {
-        Box::new(f.transform(*self))
+        ManuallyDrop::new(f.transform(ManuallyDrop::into_inner(self)))
     }
 
     #[inline]
@@ -173,6 +705,16 @@ where
         each(query, r);
     }
 
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(&**self);
+        each(query, r);
+    }
+
     #[inline]
     fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
     where
@@ -182,386 +724,2524 @@ where
         let r = mutation.mutate(&mut **self);
         each(mutation, r);
     }
-}
 
-macro_rules! impl_iter_term {
-    ($iter:ty) => {
-        impl <T> Term for $iter
-        where
-            $iter: IntoIterator<Item = T> + FromIterator<T>,
-            for <'b> &'b $iter: IntoIterator<Item = &'b T>,
-            for <'b> &'b mut $iter: IntoIterator<Item = &'b mut T>,
-            T: Term
-        {
-            fn map_one_transform<F>(self, f: &mut F) -> $iter
-            where
-                F: GenericTransform
-            {
-                self.into_iter().map(|x| f.transform(x)).collect()
-            }
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(&mut **self);
+        each(mutation, r);
+    }
 
-            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
-            where
-                Q: GenericQuery<R>,
-                F: FnMut(&mut Q, R)
-            {
-                self.into_iter().for_each(|t| {
-                    let r = query.query(t);
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let r = consume.consume(ManuallyDrop::into_inner(self));
+        each(consume, r);
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<ManuallyDrop<T>> {
+        (**self).clone_term().map(ManuallyDrop::new)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Term for PoisonError<T>
+where
+    T: Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> PoisonError<T>
+    where
+        F: GenericTransform,
+    {
+        PoisonError::new(f.transform(self.into_inner()))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(self.get_ref());
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(self.get_ref());
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(self.get_mut());
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(self.get_mut());
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let r = consume.consume(self.into_inner());
+        each(consume, r);
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<PoisonError<T>> {
+        self.get_ref().clone_term().map(PoisonError::new)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Term for Vec<T>
+where
+    T: Term,
+{
+    #[inline]
+    fn map_one_transform<F>(mut self, f: &mut F) -> Vec<T>
+    where
+        F: GenericTransform,
+    {
+        self.drain(..).map(|t| f.transform(t)).collect()
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter()
+            .map(|t| {
+                let r = query.query(t);
+                each(query, r);
+            })
+            .count();
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut()
+            .map(|t| {
+                let r = mutation.mutate(t);
+                each(mutation, r);
+            })
+            .count();
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        self.into_iter().for_each(|t| {
+            let r = consume.consume(t);
+            each(consume, r);
+        });
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<Vec<T>> {
+        self.iter().map(Term::clone_term).collect()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Term for Box<T>
+where
+    T: Sized + Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Box<T>
+    where
+        F: GenericTransform,
+    {
+        Box::new(f.transform(*self))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(&**self);
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(&**self);
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(&mut **self);
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(&mut **self);
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let r = consume.consume(*self);
+        each(consume, r);
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<Box<T>> {
+        Term::clone_term(&**self).map(Box::new)
+    }
+}
+
+impl<T, const N: usize> Term for [T; N]
+where
+    T: Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> [T; N]
+    where
+        F: GenericTransform,
+    {
+        self.map(|t| f.transform(t))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        IntoIterator::into_iter(self).for_each(|t| {
+            let r = consume.consume(t);
+            each(consume, r);
+        });
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<[T; N]> {
+        let ts = self.each_ref().map(Term::clone_term);
+        if ts.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(ts.map(Option::unwrap))
+    }
+}
+
+/// A shared slice can only be queried. Transformations, mutations, and
+/// consumers treat it as a leaf, since its elements cannot be changed or moved
+/// out of it. So `Term::holes` finds holes among its elements, but they cannot
+/// be filled.
+impl<'b, T> Term for &'b [T]
+where
+    T: Term,
+    &'b [T]: Castable,
+{
+    #[inline]
+    fn map_one_transform<F>(self, _: &mut F) -> &'b [T]
+    where
+        F: GenericTransform,
+    {
+        self
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, _: &mut M, _: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {}
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {}
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, _: &mut C, _: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {}
+
+    #[inline]
+    fn clone_term(&self) -> Option<&'b [T]> {
+        Some(*self)
+    }
+}
+
+/// A mutable slice can be queried and mutated in place. Transformations and
+/// consumers treat it as a leaf, since its elements cannot be moved out of it,
+/// so holes among its elements cannot be filled either.
+impl<'b, T> Term for &'b mut [T]
+where
+    T: Term,
+    &'b mut [T]: Castable,
+{
+    #[inline]
+    fn map_one_transform<F>(self, _: &mut F) -> &'b mut [T]
+    where
+        F: GenericTransform,
+    {
+        self
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, _: &mut C, _: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {}
+}
+
+/// Transforming a boxed slice moves its elements through a `Vec`, which the
+/// standard library may collect back into the same allocation, but does not
+/// promise to.
+#[cfg(feature = "alloc")]
+impl<T> Term for Box<[T]>
+where
+    T: Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Box<[T]>
+    where
+        F: GenericTransform,
+    {
+        self.into_vec()
+            .into_iter()
+            .map(|t| f.transform(t))
+            .collect::<Vec<T>>()
+            .into_boxed_slice()
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        self.iter_mut().for_each(|t| {
+            let r = mutation.mutate(t);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        self.into_vec().into_iter().for_each(|t| {
+            let r = consume.consume(t);
+            each(consume, r);
+        });
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<Box<[T]>> {
+        self.iter()
+            .map(Term::clone_term)
+            .collect::<Option<Vec<T>>>()
+            .map(Vec::into_boxed_slice)
+    }
+}
+
+#[cfg(feature = "alloc")]
+macro_rules! impl_shared_slice_term {
+    ( $name:ident, $into_vec:ident ) => {
+        /// Move the elements out of a slice that is not shared with any other
+        /// pointer, or clone them out of one that is, giving the slice back if
+        /// they cannot be cloned.
+        fn $into_vec<T>(mut slice: $name<[T]>) -> Result<Vec<T>, $name<[T]>>
+        where
+            T: Term,
+        {
+            if $name::get_mut(&mut slice).is_none() {
+                return slice.iter().map(Term::clone_term).collect::<Option<_>>().ok_or(slice);
+            }
+            // Safe because `ManuallyDrop<T>` has the same layout as `T`, and
+            // the slice is not shared, so nothing else sees its elements
+            // moved out. The emptied slice is dropped without dropping them.
+            let mut slice = unsafe {
+                $name::from_raw($name::into_raw(slice) as *const [ManuallyDrop<T>])
+            };
+            let elements = $name::get_mut(&mut slice).unwrap().iter_mut();
+            Ok(elements.map(|t| unsafe { ManuallyDrop::take(t) }).collect())
+        }
+
+        /// A slice that is not shared with any other pointer is traversed like
+        /// a `Box<[T]>`. Otherwise, like `Rc<T>` and `Arc<T>`, its elements are
+        /// reached through `Term::map_one_mutation_shared` if they are cells
+        /// or locks, and are cloned-on-write with `Term::clone_term` if not,
+        /// leaving the other pointers untouched. A shared slice whose elements
+        /// can be neither is left untouched, and an `Inaccessible::Shared` is
+        /// visited in its place, except by `map_one_mutation_ref`, which skips
+        /// it.
+        impl<T> Term for $name<[T]>
+        where
+            T: Term,
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> $name<[T]>
+            where
+                F: GenericTransform,
+            {
+                match $into_vec(self) {
+                    Ok(elements) => elements.into_iter().map(|t| f.transform(t)).collect(),
+                    Err(shared) => {
+                        f.transform(Inaccessible::Shared);
+                        shared
+                    }
+                }
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.iter().for_each(|t| {
+                    let r = query.query(t);
+                    each(query, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.iter().for_each(|t| {
+                    let r = query.query(t);
+                    each(query, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                if $name::get_mut(self).is_none() {
+                    if self.map_one_mutation_shared(mutation, &mut each) {
+                        return;
+                    }
+                    match self.iter().map(Term::clone_term).collect::<Option<Vec<T>>>() {
+                        Some(elements) => *self = $name::from(elements),
+                        None => {
+                            let r = mutation.mutate(&mut Inaccessible::Shared);
+                            each(mutation, r);
+                            return;
+                        }
+                    }
+                }
+                $name::get_mut(self).unwrap().iter_mut().for_each(|t| {
+                    let r = mutation.mutate(t);
+                    each(mutation, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                if $name::get_mut(self).is_none() {
+                    match self.iter().map(Term::clone_term).collect::<Option<Vec<T>>>() {
+                        Some(elements) => *self = $name::from(elements),
+                        None => return,
+                    }
+                }
+                $name::get_mut(self).unwrap().iter_mut().for_each(|t| {
+                    let r = mutation.mutate(t);
+                    each(mutation, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                match $into_vec(self) {
+                    Ok(elements) => elements.into_iter().for_each(|t| {
+                        let r = consume.consume(t);
+                        each(consume, r);
+                    }),
+                    Err(_) => {
+                        let r = consume.consume(Inaccessible::Shared);
+                        each(consume, r);
+                    }
+                }
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<$name<[T]>> {
+                Some(self.clone())
+            }
+
+            #[inline]
+            fn map_one_mutation_shared<M, R, F>(&self, mutation: &mut M, mut each: F) -> bool
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                // Every element is of the same type, so either all of them can
+                // be mutated through a shared reference, or none can.
+                self.iter().all(|t| t.map_one_mutation_shared(mutation, &mut each))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl_shared_slice_term!(Rc, rc_slice_into_vec);
+#[cfg(feature = "alloc")]
+impl_shared_slice_term!(Arc, arc_slice_into_vec);
+
+/// A borrowed `Cow` is a leaf, since whatever it borrows is shared with others.
+/// Only an owned `Cow`'s value is traversed. Like the other borrowed types, a
+/// `Cow` is a `Term` for any lifetime with the `nightly` feature, and only for
+/// `'static` without it.
+#[cfg(feature = "alloc")]
+impl<'b, B> Term for Cow<'b, B>
+where
+    B: ?Sized + ToOwned,
+    B::Owned: Term,
+    Cow<'b, B>: Castable,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Cow<'b, B>
+    where
+        F: GenericTransform,
+    {
+        match self {
+            Cow::Borrowed(b) => Cow::Borrowed(b),
+            Cow::Owned(o) => Cow::Owned(f.transform(o)),
+        }
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        if let Cow::Owned(ref o) = *self {
+            let r = query.query(o);
+            each(query, r);
+        }
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        if let Cow::Owned(ref o) = *self {
+            let r = query.query(o);
+            each(query, r);
+        }
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        if let Cow::Owned(ref mut o) = *self {
+            let r = mutation.mutate(o);
+            each(mutation, r);
+        }
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        if let Cow::Owned(ref mut o) = *self {
+            let r = mutation.mutate(o);
+            each(mutation, r);
+        }
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        if let Cow::Owned(o) = self {
+            let r = consume.consume(o);
+            each(consume, r);
+        }
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<Cow<'b, B>> {
+        Some(self.clone())
+    }
+}
+
+#[cfg(feature = "alloc")]
+macro_rules! impl_shared_term {
+    ( $name:ident ) => {
+        /// A pointer that is not shared with any other is traversed like a
+        /// `Box`. Otherwise, its value cannot be moved out or mutably borrowed,
+        /// so transformations, mutations, and consumers clone-on-write: the
+        /// value is cloned with `Term::clone_term`, as with `make_mut`, and the
+        /// other pointers are left untouched.
+        ///
+        /// Cells and locks are never cloned. Mutations reach the value inside
+        /// a shared `Cell`, `RefCell`, `Mutex`, or `RwLock` through
+        /// `Term::map_one_mutation_shared` instead, so that every pointer sees
+        /// the change. A shared value that can be neither cloned nor mutated
+        /// that way is left untouched, and an `Inaccessible::Shared` is visited
+        /// in its place, except by `map_one_mutation_ref`, which skips it.
+        impl<T> Term for $name<T>
+        where
+            T: Term,
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> $name<T>
+            where
+                F: GenericTransform,
+            {
+                let t = match $name::try_unwrap(self) {
+                    Ok(t) => t,
+                    Err(shared) => match Term::clone_term(&*shared) {
+                        Some(t) => t,
+                        None => {
+                            f.transform(Inaccessible::Shared);
+                            return shared;
+                        }
+                    },
+                };
+                $name::new(f.transform(t))
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                let r = query.query(&**self);
+                each(query, r);
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                let r = query.query(&**self);
+                each(query, r);
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                if $name::get_mut(self).is_none() {
+                    if (**self).map_one_mutation_shared(mutation, &mut each) {
+                        return;
+                    }
+                    match Term::clone_term(&**self) {
+                        Some(t) => *self = $name::new(t),
+                        None => {
+                            let r = mutation.mutate(&mut Inaccessible::Shared);
+                            each(mutation, r);
+                            return;
+                        }
+                    }
+                }
+                let r = mutation.mutate($name::get_mut(self).unwrap());
+                each(mutation, r);
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                if $name::get_mut(self).is_none() {
+                    match Term::clone_term(&**self) {
+                        Some(t) => *self = $name::new(t),
+                        None => return,
+                    }
+                }
+                let r = mutation.mutate($name::get_mut(self).unwrap());
+                each(mutation, r);
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                let r = match $name::try_unwrap(self) {
+                    Ok(t) => consume.consume(t),
+                    Err(shared) => match Term::clone_term(&*shared) {
+                        Some(t) => consume.consume(t),
+                        None => consume.consume(Inaccessible::Shared),
+                    },
+                };
+                each(consume, r);
+            }
+
+            #[inline]
+            fn clone_term(&self) -> Option<$name<T>> {
+                Some(self.clone())
+            }
+
+            #[inline]
+            fn map_one_mutation_shared<M, R, F>(&self, mutation: &mut M, each: F) -> bool
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                (**self).map_one_mutation_shared(mutation, each)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl_shared_term!(Rc);
+#[cfg(feature = "alloc")]
+impl_shared_term!(Arc);
+
+/// A `Cell`'s value is queried, and mutated by `map_one_mutation_shared`, by
+/// copy. Nothing inside a `Cell` can be borrowed for as long as the `Cell`, so
+/// `map_one_query_ref` visits an `Inaccessible::Interior` instead.
+impl<T> Term for Cell<T>
+where
+    T: Copy + Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Cell<T>
+    where
+        F: GenericTransform,
+    {
+        Cell::new(f.transform(self.into_inner()))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(&self.get());
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(&Inaccessible::Interior);
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(self.get_mut());
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(self.get_mut());
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let r = consume.consume(self.into_inner());
+        each(consume, r);
+    }
+
+    #[inline]
+    fn map_one_mutation_shared<M, R, F>(&self, mutation: &mut M, mut each: F) -> bool
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let mut t = self.get();
+        let r = mutation.mutate(&mut t);
+        self.set(t);
+        each(mutation, r);
+        true
+    }
+}
+
+/// A `RefCell` is borrowed for the duration of `map_one_query`, and an
+/// `Inaccessible::Borrowed` is queried instead if it is already mutably
+/// borrowed. Likewise, `map_one_mutation_shared` borrows it mutably, and
+/// mutates an `Inaccessible::Borrowed` instead if it is already borrowed.
+/// `map_one_query_ref` visits an `Inaccessible::Interior` instead of its
+/// value, since the borrow cannot outlive the call.
+impl<T> Term for RefCell<T>
+where
+    T: Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> RefCell<T>
+    where
+        F: GenericTransform,
+    {
+        RefCell::new(f.transform(self.into_inner()))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = match self.try_borrow() {
+            Ok(t) => query.query(&*t),
+            Err(_) => query.query(&Inaccessible::Borrowed),
+        };
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = query.query(&Inaccessible::Interior);
+        each(query, r);
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(self.get_mut());
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = mutation.mutate(self.get_mut());
+        each(mutation, r);
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let r = consume.consume(self.into_inner());
+        each(consume, r);
+    }
+
+    #[inline]
+    fn map_one_mutation_shared<M, R, F>(&self, mutation: &mut M, mut each: F) -> bool
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = match self.try_borrow_mut() {
+            Ok(mut t) => mutation.mutate(&mut *t),
+            Err(_) => mutation.mutate(&mut Inaccessible::Borrowed),
+        };
+        each(mutation, r);
+        true
+    }
+}
+
+#[cfg(feature = "std")]
+macro_rules! impl_lock_term {
+    ( $name:ident, $try_lock:ident, $try_lock_mut:ident ) => {
+        /// Locks are never waited on: `map_one_query` and
+        /// `map_one_mutation_shared` try to take the lock, and visit an
+        /// `Inaccessible::Locked` instead if that would block.
+        /// A poisoned lock's value is left untouched, and an
+        /// `Inaccessible::Poisoned` is visited in its place, except by
+        /// `map_one_mutation_ref`, which skips it. As with `RefCell`,
+        /// `map_one_query_ref` visits an `Inaccessible::Interior` instead of
+        /// the value.
+        impl<T> Term for $name<T>
+        where
+            T: Term,
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> $name<T>
+            where
+                F: GenericTransform,
+            {
+                if self.is_poisoned() {
+                    f.transform(Inaccessible::Poisoned);
+                    return self;
+                }
+                match self.into_inner() {
+                    Ok(t) => $name::new(f.transform(t)),
+                    Err(_) => unreachable!("the lock was not poisoned, and we own it"),
+                }
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                let r = match self.$try_lock() {
+                    Ok(t) => query.query(&*t),
+                    Err(TryLockError::WouldBlock) => query.query(&Inaccessible::Locked),
+                    Err(TryLockError::Poisoned(_)) => query.query(&Inaccessible::Poisoned),
+                };
+                each(query, r);
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                let r = query.query(&Inaccessible::Interior);
+                each(query, r);
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                let r = match self.get_mut() {
+                    Ok(t) => mutation.mutate(t),
+                    Err(_) => mutation.mutate(&mut Inaccessible::Poisoned),
+                };
+                each(mutation, r);
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                if let Ok(t) = self.get_mut() {
+                    let r = mutation.mutate(t);
+                    each(mutation, r);
+                }
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                let r = match self.into_inner() {
+                    Ok(t) => consume.consume(t),
+                    Err(_) => consume.consume(Inaccessible::Poisoned),
+                };
+                each(consume, r);
+            }
+
+            #[inline]
+            fn map_one_mutation_shared<M, R, F>(&self, mutation: &mut M, mut each: F) -> bool
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                let r = match self.$try_lock_mut() {
+                    Ok(mut t) => mutation.mutate(&mut *t),
+                    Err(TryLockError::WouldBlock) => mutation.mutate(&mut Inaccessible::Locked),
+                    Err(TryLockError::Poisoned(_)) => mutation.mutate(&mut Inaccessible::Poisoned),
+                };
+                each(mutation, r);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl_lock_term!(Mutex, try_lock, try_lock);
+#[cfg(feature = "std")]
+impl_lock_term!(RwLock, try_read, try_write);
+
+#[cfg(feature = "alloc")]
+impl_term_collection!([T] LinkedList<T>);
+#[cfg(feature = "alloc")]
+impl_term_collection!([T] VecDeque<T>);
+
+/// Rebuilds a binary heap from its `elements` when dropped, after they have
+/// been mutated, or if a mutation panics, so that the heap never loses them.
+#[cfg(feature = "alloc")]
+struct HeapGuard<'h, T>
+where
+    T: 'h + Ord,
+{
+    heap: &'h mut BinaryHeap<T>,
+    elements: Vec<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'h, T> Drop for HeapGuard<'h, T>
+where
+    T: Ord,
+{
+    fn drop(&mut self) {
+        *self.heap = mem::take(&mut self.elements).into();
+    }
+}
+
+/// A binary heap is traversed in its internal order, which is unspecified, but
+/// only depends on the sequence of operations that built the heap, so it is
+/// the same from one run of the program to the next. To visit the elements in
+/// sorted order, traverse the heap's `into_sorted_vec` instead.
+///
+/// Elements are moved in and out of the heap to transform or mutate them, and
+/// the heap is rebuilt afterwards, even if a mutation panics. Since
+/// `map_one_mutation_ref` cannot rebuild the heap, it does not visit any
+/// elements.
+#[cfg(feature = "alloc")]
+impl<T> Term for BinaryHeap<T>
+where
+    T: Ord + Term,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> BinaryHeap<T>
+    where
+        F: GenericTransform,
+    {
+        self.into_vec().map_one_transform(f).into()
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        self.iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let mut guard = HeapGuard {
+            elements: mem::take(self).into_vec(),
+            heap: self,
+        };
+        guard.elements.map_one_mutation(mutation, each);
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {}
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        self.into_vec().map_one_consume(consume, each);
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<BinaryHeap<T>> {
+        self.iter()
+            .map(Term::clone_term)
+            .collect::<Option<Vec<T>>>()
+            .map(BinaryHeap::from)
+    }
+}
+
+/// Guards a map whose values are being transformed in place by
+/// `transform_value`. If a transformation panics, the guard drops every value
+/// but the one that was moved out of its slot, at `hole`, and the map itself is
+/// leaked along with its keys, so that the moved-out value is not dropped
+/// twice.
+#[cfg(feature = "alloc")]
+struct ValuesGuard<'m, M: 'm, V> {
+    map: &'m mut M,
+    hole: *const V,
+    drop_others: fn(&mut M, *const V),
+}
+
+#[cfg(feature = "alloc")]
+impl<'m, M, V> Drop for ValuesGuard<'m, M, V> {
+    fn drop(&mut self) {
+        (self.drop_others)(self.map, self.hole);
+    }
+}
+
+/// Drop each of a guarded map's `values` in place, except for the `hole`.
+#[cfg(feature = "alloc")]
+fn drop_values_but<'a, V, I>(values: I, hole: *const V)
+where
+    V: 'a,
+    I: IntoIterator<Item = &'a mut V>,
+{
+    for v in values {
+        if !ptr::eq(v, hole) {
+            // Safe because the guarded map is leaked, so nothing reads or
+            // drops the value again.
+            unsafe { ptr::drop_in_place(v) };
+        }
+    }
+}
+
+/// Transform the value `v` within a guarded map in place, marking its slot as
+/// the guard's `hole` while the value is moved out.
+#[cfg(feature = "alloc")]
+fn transform_value<V, F>(hole: &mut *const V, v: &mut V, f: &mut F)
+where
+    V: Term,
+    F: GenericTransform,
+{
+    *hole = v;
+    // Safe because the slot is written back before anything reads it again,
+    // and if the transformation panics, the guard never drops the slot.
+    unsafe {
+        let t = ptr::read(v);
+        ptr::write(v, f.transform(t));
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S> Term for Sorted<HashMap<K, V, S>>
+where
+    K: Castable + Eq + Hash + Ord,
+    V: Term,
+    S: Castable + BuildHasher + Clone,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Sorted<HashMap<K, V, S>>
+    where
+        F: GenericTransform,
+    {
+        let mut map = ManuallyDrop::new(self.0);
+        {
+            let mut guard = ValuesGuard {
+                map: &mut *map,
+                hole: ptr::null(),
+                drop_others: |map: &mut HashMap<K, V, S>, hole| {
+                    drop_values_but(map.values_mut(), hole)
+                },
+            };
+            let mut entries: Vec<_> = guard.map.iter_mut().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (_, v) in entries {
+                transform_value(&mut guard.hole, v, f);
+            }
+            mem::forget(guard);
+        }
+        Sorted(ManuallyDrop::into_inner(map))
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.into_iter().for_each(|(_, v)| {
+            let r = query.query(v);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.into_iter().for_each(|(_, v)| {
+            let r = query.query(v);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let mut entries: Vec<_> = self.0.iter_mut().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.into_iter().for_each(|(_, v)| {
+            let r = mutation.mutate(v);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let mut entries: Vec<_> = self.0.iter_mut().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.into_iter().for_each(|(_, v)| {
+            let r = mutation.mutate(v);
+            each(mutation, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let mut entries: Vec<_> = self.0.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.into_iter().for_each(|(_, v)| {
+            let r = consume.consume(v);
+            each(consume, r);
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, S> Term for Sorted<HashSet<T, S>>
+where
+    T: Eq + Hash + Ord + Term,
+    S: Castable + BuildHasher + Clone,
+{
+    #[inline]
+    fn map_one_transform<F>(self, f: &mut F) -> Sorted<HashSet<T, S>>
+    where
+        F: GenericTransform,
+    {
+        let mut set = HashSet::with_hasher(self.0.hasher().clone());
+        let mut elements: Vec<_> = self.0.into_iter().collect();
+        elements.sort();
+        for t in elements {
+            set.insert(f.transform(t));
+        }
+        Sorted(set)
+    }
+
+    #[inline]
+    fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let mut elements: Vec<_> = self.0.iter().collect();
+        elements.sort();
+        elements.into_iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let mut elements: Vec<_> = self.0.iter().collect();
+        elements.sort();
+        elements.into_iter().for_each(|t| {
+            let r = query.query(t);
+            each(query, r);
+        });
+    }
+
+    #[inline]
+    fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let empty = HashSet::with_hasher(self.0.hasher().clone());
+        let mut elements: Vec<_> = mem::replace(&mut self.0, empty).into_iter().collect();
+        elements.sort();
+        for mut t in elements {
+            let r = mutation.mutate(&mut t);
+            each(mutation, r);
+            self.0.insert(t);
+        }
+    }
+
+    #[inline]
+    fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {}
+
+    #[inline]
+    fn map_one_consume<C, R, F>(self, consume: &mut C, each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let mut elements: Vec<_> = self.0.into_iter().collect();
+        elements.sort();
+        elements.map_one_consume(consume, each);
+    }
+
+    #[inline]
+    fn clone_term(&self) -> Option<Sorted<HashSet<T, S>>> {
+        Term::clone_term(&self.0).map(Sorted)
+    }
+}
+
+/// A map's keys are fixed, and only its values are traversed, in place: the
+/// map is never rebuilt, and no key is hashed or compared again. Wrap the map
+/// in `Keyed` to traverse its keys, too, which rebuilds the map, so a
+/// `HashMap`'s `BuildHasher` must be `Clone` to give the new map a clone of it.
+#[cfg(feature = "alloc")]
+macro_rules! impl_map_term {
+    ( $map:ty, [ $( $params:ident ),* ], [ $( $bounds:tt )* ], | $m:ident | $empty:expr ) => {
+        impl< $( $params ),* > Term for $map
+        where
+            V: Term,
+            $( $bounds )*
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> $map
+            where
+                F: GenericTransform,
+            {
+                let mut map = ManuallyDrop::new(self);
+                {
+                    let mut guard = ValuesGuard {
+                        map: &mut *map,
+                        hole: ptr::null(),
+                        drop_others: |map: &mut $map, hole| drop_values_but(map.values_mut(), hole),
+                    };
+                    for v in guard.map.values_mut() {
+                        transform_value(&mut guard.hole, v, f);
+                    }
+                    mem::forget(guard);
+                }
+                ManuallyDrop::into_inner(map)
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.values().for_each(|v| {
+                    let r = query.query(v);
+                    each(query, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.values().for_each(|v| {
+                    let r = query.query(v);
+                    each(query, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                self.values_mut().for_each(|v| {
+                    let r = mutation.mutate(v);
+                    each(mutation, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                self.values_mut().for_each(|v| {
+                    let r = mutation.mutate(v);
+                    each(mutation, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                self.into_values().for_each(|v| {
+                    let r = consume.consume(v);
+                    each(consume, r);
+                });
+            }
+        }
+
+        impl< $( $params ),* > Term for Keyed<$map>
+        where
+            K: Term,
+            V: Term,
+            $( $bounds )*
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> Keyed<$map>
+            where
+                F: GenericTransform,
+            {
+                let (old, mut collisions) = self.into_parts();
+                let mut map = {
+                    let $m = &old;
+                    $empty
+                };
+                for (k, v) in old {
+                    let k = f.transform(k);
+                    let v = f.transform(v);
+                    if map.contains_key(&k) {
+                        collisions.push((k, v));
+                    } else {
+                        map.insert(k, v);
+                    }
+                }
+                Keyed::from_parts(map, collisions)
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.collection().iter().for_each(|(k, v)| {
+                    let r = query.query(k);
+                    each(query, r);
+                    let r = query.query(v);
+                    each(query, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.collection().iter().for_each(|(k, v)| {
+                    let r = query.query(k);
+                    each(query, r);
+                    let r = query.query(v);
                     each(query, r);
                 });
             }
 
-            fn map_one_mutation<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
             where
                 M: GenericMutate<R>,
-                F: FnMut(&mut M, R)
+                F: FnMut(&mut M, R),
             {
-                self.into_iter().for_each(|t: &mut T| {
-                    let r = mutation.mutate(t);
+                let (map, collisions) = self.parts_mut();
+                let empty = {
+                    let $m = &*map;
+                    $empty
+                };
+                for (mut k, mut v) in mem::replace(map, empty) {
+                    let r = mutation.mutate(&mut k);
+                    each(mutation, r);
+                    let r = mutation.mutate(&mut v);
+                    each(mutation, r);
+                    if map.contains_key(&k) {
+                        collisions.push((k, v));
+                    } else {
+                        map.insert(k, v);
+                    }
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {
+                self.parts_mut().0.values_mut().for_each(|v| {
+                    let r = mutation.mutate(v);
                     each(mutation, r);
                 });
             }
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                self.into_parts().0.into_iter().for_each(|(k, v)| {
+                    let r = consume.consume(k);
+                    each(consume, r);
+                    let r = consume.consume(v);
+                    each(consume, r);
+                });
+            }
         }
     }
 }
 
-impl_iter_term!(LinkedList<T>);
-impl_iter_term!(HashSet<T>);
-impl_iter_term!(BTreeSet<T>);
-impl_iter_term!(BinaryHeap<T>);
-impl_iter_term!(VecDeque<T>);
-
+#[cfg(feature = "std")]
+impl_map_term!(
+    HashMap<K, V, S>,
+    [K, V, S],
+    [K: Castable + Eq + Hash, S: Castable + BuildHasher + Clone],
+    |map| HashMap::with_hasher(map.hasher().clone())
+);
+#[cfg(feature = "alloc")]
+impl_map_term!(BTreeMap<K, V>, [K, V], [K: Castable + Ord], |_map| BTreeMap::new());
 
-macro_rules! impl_iterkey_term {
-    ($iter:ty) => {
-        impl <K,T> Term for $iter
+/// Transforming or mutating a set's elements rebuilds the set, and elements
+/// that become equal are merged into one. Wrap the set in `Keyed` to set aside
+/// and report such collisions instead. `map_one_mutation_ref` cannot rebuild
+/// the set, so it does not visit any elements. A rebuilt `HashSet` gets a clone
+/// of the original's hasher, so its `BuildHasher` must be `Clone`.
+#[cfg(feature = "alloc")]
+macro_rules! impl_set_term {
+    ( $set:ty, [ $( $params:ident ),* ], [ $( $bounds:tt )* ], | $s:ident | $empty:expr ) => {
+        impl< $( $params ),* > Term for $set
         where
-            $iter: IntoIterator<Item = (K,T)> + FromIterator<(K,T)>,
-            for <'b> &'b $iter: IntoIterator<Item = &'b (K,T)>,
-            for <'b> &'b mut $iter: IntoIterator<Item = &'b mut (K,T)>,
-            (K,T): Term
+            T: Term,
+            $( $bounds )*
         {
-            fn map_one_transform<F>(self, f: &mut F) -> $iter
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> $set
             where
-                F: GenericTransform
+                F: GenericTransform,
             {
-                self.into_iter().map(|x| f.transform(x)).collect()
+                let mut set = {
+                    let $s = &self;
+                    $empty
+                };
+                for t in self {
+                    set.insert(f.transform(t));
+                }
+                set
             }
 
+            #[inline]
             fn map_one_query<Q, R, F>(&self, query: &mut Q, mut each: F)
             where
                 Q: GenericQuery<R>,
-                F: FnMut(&mut Q, R)
+                F: FnMut(&mut Q, R),
             {
-                self.into_iter().for_each(|t| {
+                self.iter().for_each(|t| {
+                    let r = query.query(t);
+                    each(query, r);
+                });
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, mut each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.iter().for_each(|t| {
                     let r = query.query(t);
                     each(query, r);
                 });
             }
 
-            fn map_one_mutation<'a, M, R, F>(&'a mut self, mutation: &mut M, mut each: F)
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
             where
                 M: GenericMutate<R>,
-                F: FnMut(&mut M, R)
+                F: FnMut(&mut M, R),
             {
-                self.into_iter().for_each(|t: &mut (K,T)| {
-                    let r = mutation.mutate(t);
+                let empty = {
+                    let $s = &*self;
+                    $empty
+                };
+                for mut t in mem::replace(self, empty) {
+                    let r = mutation.mutate(&mut t);
                     each(mutation, r);
+                    self.insert(t);
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {}
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, mut each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                self.into_iter().for_each(|t| {
+                    let r = consume.consume(t);
+                    each(consume, r);
                 });
             }
+
+            #[inline]
+            fn clone_term(&self) -> Option<$set> {
+                let mut set = {
+                    let $s = self;
+                    $empty
+                };
+                for t in self {
+                    set.insert(Term::clone_term(t)?);
+                }
+                Some(set)
+            }
+        }
+
+        impl< $( $params ),* > Term for Keyed<$set>
+        where
+            T: Term,
+            $( $bounds )*
+        {
+            #[inline]
+            fn map_one_transform<F>(self, f: &mut F) -> Keyed<$set>
+            where
+                F: GenericTransform,
+            {
+                let (old, mut collisions) = self.into_parts();
+                let mut set = {
+                    let $s = &old;
+                    $empty
+                };
+                for t in old {
+                    let t = f.transform(t);
+                    if set.contains(&t) {
+                        collisions.push(t);
+                    } else {
+                        set.insert(t);
+                    }
+                }
+                Keyed::from_parts(set, collisions)
+            }
+
+            #[inline]
+            fn map_one_query<Q, R, F>(&self, query: &mut Q, each: F)
+            where
+                Q: GenericQuery<R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.collection().map_one_query(query, each);
+            }
+
+            #[inline]
+            fn map_one_query_ref<'a, Q, R, F>(&'a self, query: &mut Q, each: F)
+            where
+                Q: GenericQueryRef<'a, R>,
+                F: FnMut(&mut Q, R),
+            {
+                self.collection().map_one_query_ref(query, each);
+            }
+
+            #[inline]
+            fn map_one_mutation<M, R, F>(&mut self, mutation: &mut M, mut each: F)
+            where
+                M: GenericMutate<R>,
+                F: FnMut(&mut M, R),
+            {
+                let (set, collisions) = self.parts_mut();
+                let empty = {
+                    let $s = &*set;
+                    $empty
+                };
+                for mut t in mem::replace(set, empty) {
+                    let r = mutation.mutate(&mut t);
+                    each(mutation, r);
+                    if set.contains(&t) {
+                        collisions.push(t);
+                    } else {
+                        set.insert(t);
+                    }
+                }
+            }
+
+            #[inline]
+            fn map_one_mutation_ref<'a, M, R, F>(&'a mut self, _: &mut M, _: F)
+            where
+                M: GenericMutateRef<'a, R>,
+                F: FnMut(&mut M, R),
+            {}
+
+            #[inline]
+            fn map_one_consume<C, R, F>(self, consume: &mut C, each: F)
+            where
+                C: GenericConsume<R>,
+                F: FnMut(&mut C, R),
+            {
+                self.into_parts().0.map_one_consume(consume, each);
+            }
         }
     }
 }
 
-impl_iterkey_term!(HashMap<K,T>);
-impl_iterkey_term!(BTreeMap<K,T>);
+#[cfg(feature = "std")]
+impl_set_term!(
+    HashSet<T, S>,
+    [T, S],
+    [T: Eq + Hash, S: Castable + BuildHasher + Clone],
+    |set| HashSet::with_hasher(set.hasher().clone())
+);
+#[cfg(feature = "alloc")]
+impl_set_term!(BTreeSet<T>, [T], [T: Ord], |_set| BTreeSet::new());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::*;
+
+    #[test]
+    fn core_enums() {
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        assert_eq!(incr.transform((Some(1), None::<i32>)), (Some(2), None));
+        assert_eq!(incr.transform(Ok::<i32, i32>(1)), Ok(2));
+        assert_eq!(incr.transform(Err::<i32, i32>(1)), Err(2));
+        assert_eq!(incr.transform(Bound::Excluded(1)), Bound::Excluded(2));
+        assert_eq!(incr.transform(Bound::Unbounded::<i32>), Bound::Unbounded);
+        assert_eq!(incr.transform((Reverse(1), Wrapping(1))), (Reverse(2), Wrapping(2)));
+        assert_eq!(incr.transform(cmp::Ordering::Less), cmp::Ordering::Less);
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        let value = (Some(Ok::<i32, i32>(1)), Bound::Included(Reverse(2)), Wrapping(3));
+        assert_eq!(sum.query(&value), 6);
+
+        let mut value = (Some(Err::<i32, i32>(1)), Bound::Excluded(Wrapping(2)));
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut value);
+        assert_eq!(value, (Some(Err(0)), Bound::Excluded(Wrapping(0))));
+    }
+
+    #[test]
+    fn ranges() {
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        assert_eq!(incr.transform(1..2), 2..3);
+        assert_eq!(incr.transform((1.., ..2, ..=3)), (2.., ..3, ..=4));
+        assert_eq!(incr.transform(1..=2), 2..=3);
+
+        let mut next = Everywhere::new(Transformation::new(|c: char| (c as u8 + 1) as char));
+        assert_eq!(next.transform(('a'..'c', 'a'..='c')), ('b'..'d', 'b'..='d'));
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&(1..2, 3.., ..4, ..=5)), 15);
+        assert_eq!(sum.query(&(6..=7)), 13);
+
+        let mut value = (1..2, 3.., ..4, ..=5, 6..=7);
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut value);
+        assert_eq!(value, (0..0, 0.., ..0, ..=0, 0..=0));
+    }
+
+    #[test]
+    fn manually_drop() {
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        assert_eq!(incr.transform(ManuallyDrop::new(1)), ManuallyDrop::new(2));
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&ManuallyDrop::new((1, 2))), 3);
+
+        let mut value = ManuallyDrop::new(Some(1));
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut value);
+        assert_eq!(*value, Some(0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn sync_errors() {
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let poisoned = incr.transform(TryLockError::Poisoned(PoisonError::new(1)));
+        match poisoned {
+            TryLockError::Poisoned(e) => assert_eq!(e.into_inner(), 2),
+            TryLockError::WouldBlock => panic!("expected a poisoned lock"),
+        }
+        assert_eq!(incr.transform(SendError(1)), SendError(2));
+        assert_eq!(incr.transform(TrySendError::Full(1)), TrySendError::Full(2));
+        assert_eq!(incr.transform(TrySendError::Disconnected(1)), TrySendError::Disconnected(2));
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        let value = (PoisonError::new(1), SendError(2), TrySendError::Full(3));
+        assert_eq!(sum.query(&value), 6);
+        assert_eq!(sum.query(&TryLockError::WouldBlock::<i32>), 0);
+
+        let mut value = (TryLockError::Poisoned(PoisonError::new(1)), SendError(2));
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut value);
+        assert_eq!(sum.query(&value), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn cow() {
+        static BORROWED: &[i32] = &[1, 2];
+        let value: (Cow<'static, [i32]>, Cow<'static, [i32]>) =
+            (Cow::Borrowed(BORROWED), Cow::Owned(vec![1, 2]));
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&value), 3);
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let (borrowed, owned) = incr.transform(value);
+        assert_eq!((&*borrowed, &*owned), (&[1, 2][..], &[2, 3][..]));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn shared_pointers() {
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let (a, b) = incr.transform((Rc::new(1), Arc::new(2)));
+        assert_eq!((*a, *b), (2, 3));
+
+        let (c, d) = incr.transform((a.clone(), b.clone()));
+        assert_eq!((*a, *b, *c, *d), (2, 3, 3, 4));
+
+        let mut value = (a.clone(), b.clone());
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut value);
+        assert_eq!((*value.0, *value.1, *a, *b), (0, 0, 2, 3));
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&(a.clone(), a)), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn shared_slices_clone_on_write() {
+        let shared: Rc<[i32]> = Rc::from(vec![1, 2]);
+        let other = shared.clone();
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let transformed = incr.transform(shared.clone());
+        assert_eq!((&*shared, &*transformed), (&[1, 2][..], &[2, 3][..]));
+
+        let mut value = (shared, Arc::<[i32]>::from(vec![3]));
+        let arc = value.1.clone();
+        for n in value.collect_mut::<i32>() {
+            *n *= 10;
+        }
+        assert_eq!((&*other, &*value.0), (&[1, 2][..], &[10, 20][..]));
+        assert_eq!((&*arc, &*value.1), (&[3][..], &[30][..]));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn shared_slices_without_clone() {
+        #[derive(Debug, PartialEq)]
+        struct Counter(i32);
+        impl_term!(struct [] Counter { 0 });
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let unique: Rc<[Counter]> = Rc::from(vec![Counter(1), Counter(2)]);
+        assert_eq!(&*incr.transform(unique), &[Counter(2), Counter(3)][..]);
+
+        // A shared slice whose elements cannot be cloned is left untouched.
+        let shared: Arc<[Counter]> = Arc::from(vec![Counter(1)]);
+        let mut value = shared.clone();
+        let mut found = vec![];
+        MutateEverything::new(Mutation::new(|i: &mut Inaccessible| found.push(*i)))
+            .mutate(&mut value);
+        assert_eq!(found, vec![Inaccessible::Shared]);
+        assert!(Arc::ptr_eq(&incr.transform(value), &shared));
+
+        // Mutations reach through a shared slice of cells, for every pointer.
+        let mut cells = Rc::<[RefCell<i32>]>::from(vec![RefCell::new(1)]);
+        let other = cells.clone();
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut cells);
+        assert_eq!(*other[0].borrow(), 0);
+
+        // Elements moved out of a unique slice are dropped exactly once.
+        let element = Rc::new(1);
+        let unique: Rc<[Rc<i32>]> = Rc::from(vec![element.clone(), element.clone()]);
+        drop(incr.transform(unique));
+        assert_eq!(Rc::strong_count(&element), 1);
+    }
+
+    #[test]
+    fn arrays_and_slices() {
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        assert_eq!(incr.transform([[1, 2], [3, 4]]), [[2, 3], [4, 5]]);
+
+        static SLICE: &[i32] = &[1, 2, 3];
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&([1, 2], SLICE)), 9);
+        assert_eq!(incr.transform(SLICE), &[1, 2, 3]);
+
+        let mut array = [(1, 'a'), (2, 'b')];
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut array);
+        assert_eq!(array, [(0, 'a'), (0, 'b')]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn boxed_and_shared_slices() {
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        let mut slice: &'static mut [i32] = Box::leak(vec![1, 2].into_boxed_slice());
+        zero.mutate(&mut slice);
+        assert_eq!(slice, &[0, 0]);
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let boxed: Box<[i32]> = vec![1, 2].into_boxed_slice();
+        assert_eq!(&*incr.transform(boxed), &[2, 3][..]);
+
+        let shared: Rc<[i32]> = Rc::from(vec![1, 2]);
+        let (a, b) = incr.transform((shared.clone(), Arc::<[i32]>::from(vec![3])));
+        assert_eq!((&*shared, &*a, &*b), (&[1, 2][..], &[2, 3][..], &[4][..]));
+
+        let mut value = (shared.clone(), a);
+        zero.mutate(&mut value);
+        assert_eq!((&*shared, &*value.0, &*value.1), (&[1, 2][..], &[0, 0][..], &[0, 0][..]));
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        let boxed_str: Box<str> = "str".into();
+        assert_eq!(sum.query(&(shared, boxed_str)), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn strings() {
+        let mut shout = Everywhere::new(Transformation::new(|s: String| s.to_uppercase()));
+        let value = (String::from("a"), Cow::Owned::<'static, str>(String::from("b")), "c");
+        assert_eq!(
+            shout.transform(value),
+            (String::from("A"), Cow::Owned(String::from("B")), "c")
+        );
+
+        let mut count = Everything::new(Query::new(|_: &PathBuf| 1), |a, b| a + b);
+        let value = (PathBuf::from("a"), Path::new("b"), OsString::from("c"), 'a'..='z');
+        assert_eq!(count.query(&(value, CString::new("d").unwrap())), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn non_static_strings() {
+        let owned = String::from("borrowed");
+        let value = (owned.as_str(), Path::new(&owned));
+        let mut count = Everything::new(Query::new(|_: &&str| 1), |a, b| a + b);
+        assert_eq!(count.query(&value), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn maps_and_sets() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+
+        type Hasher = BuildHasherDefault<DefaultHasher>;
+
+        let mut map: HashMap<i32, i32, Hasher> = HashMap::default();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        let set: BTreeSet<i32> = vec![1, 2].into_iter().collect();
 
-// TODO
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let (map, set) = incr.transform((map, set));
+        assert_eq!((map[&1], map[&2]), (11, 21));
+        assert_eq!(set, vec![2, 3].into_iter().collect());
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&(map.clone(), set)), 37);
+
+        let mut map = BTreeMap::new();
+        map.insert("a", 1);
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut map);
+        assert_eq!(map["a"], 0);
+
+        let mut set: HashSet<i32, Hasher> = vec![1, 2].into_iter().collect();
+        zero.mutate(&mut set);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn map_transform_panics() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let values: Vec<Rc<i32>> = (0..4).map(Rc::new).collect();
+        let mut fail = Transformation::new(|v: Rc<i32>| {
+            assert!(*v != 2, "cannot transform 2");
+            v
+        });
+
+        let map: BTreeMap<i32, Rc<i32>> = values.iter().map(|v| (**v, v.clone())).collect();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| map.map_one_transform(&mut fail)));
+        assert!(result.is_err());
+
+        // Every value, transformed or not, was dropped exactly once.
+        assert!(values.iter().all(|v| Rc::strong_count(v) == 1));
+
+        let map: HashMap<i32, Rc<i32>> = values.iter().map(|v| (**v, v.clone())).collect();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            Sorted(map).map_one_transform(&mut fail)
+        }));
+        assert!(result.is_err());
+        assert!(values.iter().all(|v| Rc::strong_count(v) == 1));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn map_transform_keeps_keys() {
+        use core::hash::Hasher;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static HASHES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(i32);
+
+        impl Hash for Key {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                HASHES.fetch_add(1, Ordering::SeqCst);
+                self.0.hash(state);
+            }
+        }
+
+        let map: HashMap<Key, i32> = (0..10).map(|i| (Key(i), i)).collect();
+        let hashes = HASHES.load(Ordering::SeqCst);
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let map = map.map_one_transform(&mut incr);
+        let map = Sorted(map).map_one_transform(&mut incr).into_inner();
+        assert_eq!(HASHES.load(Ordering::SeqCst), hashes);
+        assert_eq!(map[&Key(3)], 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn sorted() {
+        let map: HashMap<_, _> = (0..100).map(|i| (i, i)).collect();
+        let set: HashSet<_> = (0..100).collect();
+        let mut all = Everything::new(Query::new(|i: &i32| vec![*i]), |mut a, b| {
+            a.extend(b);
+            a
+        });
+        let sorted: Vec<_> = (0..100).collect();
+        assert_eq!(all.query(&Sorted(map.clone())), sorted);
+        assert_eq!(all.query(&Sorted(set)), sorted);
+
+        let mut order = vec![];
+        {
+            let mut record = MutateEverything::new(Mutation::new(|i: &mut i32| order.push(*i)));
+            record.mutate(&mut Sorted(map));
+        }
+        assert_eq!(order, sorted);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn sorted_set_transforms() {
+        let set: HashSet<i32> = (0..100).collect();
+        let mut order = vec![];
+        let set = {
+            let mut double = Everywhere::new(Transformation::new(|i: i32| {
+                order.push(i);
+                i * 2
+            }));
+            double.transform(Sorted(set))
+        };
+        assert_eq!(order, (0..100).collect::<Vec<_>>());
+        assert_eq!(set.0, (0..100).map(|i| i * 2).collect());
+
+        let mut set = set;
+        let mut halve = MutateEverything::new(Mutation::new(|i: &mut i32| *i /= 2));
+        halve.mutate(&mut set);
+        assert_eq!(set.0, (0..100).collect());
+
+        let holes = set.holes::<i32>();
+        assert_eq!(holes.len(), 100);
+        assert_eq!(holes[3].get(), &3);
+        let filled = holes[3].fill(1000);
+        assert!(filled.0.contains(&1000) && !filled.0.contains(&3));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn binary_heap() {
+        let heap: BinaryHeap<_> = vec![1, 5, 3].into_iter().collect();
+        let mut negate = Everywhere::new(Transformation::new(|i: i32| -i));
+        let heap = negate.transform(heap);
+        assert_eq!(heap.peek(), Some(&-1));
+
+        let mut heap = heap;
+        let mut double = MutateEverything::new(Mutation::new(|i: &mut i32| *i *= -2));
+        double.mutate(&mut heap);
+        assert_eq!(heap.into_sorted_vec(), vec![2, 6, 10]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn binary_heap_mutation_panics() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut heap: BinaryHeap<_> = vec![1, 2, 3].into_iter().collect();
+        let mut fail = MutateEverything::new(Mutation::new(|i: &mut i32| {
+            assert!(*i != 2, "cannot mutate 2");
+            *i *= 10;
+        }));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| fail.mutate(&mut heap)));
+        assert!(result.is_err());
+
+        // The heap still has every element, whether it was mutated or not.
+        let elements = heap.into_vec();
+        assert_eq!(elements.len(), 3);
+        assert!(elements.contains(&2));
+        assert!(elements.iter().any(|&i| i == 1 || i == 10));
+        assert!(elements.iter().any(|&i| i == 3 || i == 30));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn keyed() {
+        let mut map = HashMap::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let keyed = incr.transform(Keyed::new(map));
+        assert_eq!(keyed.collisions(), &[]);
+        let map = keyed.into_inner().unwrap();
+        assert_eq!((map[&2], map[&3]), (11, 21));
+
+        let mut halve = Everywhere::new(Transformation::new(|i: i32| i / 2));
+        let set: BTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+        let collision = halve.transform(Keyed::new(set)).into_inner().unwrap_err();
+        assert_eq!(collision.collection, vec![1, 2].into_iter().collect());
+        assert_eq!(collision.collisions, vec![1]);
+
+        let mut keyed = Keyed::new(map);
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut keyed);
+        assert_eq!(keyed.collection().len(), 1);
+        assert_eq!(keyed.collisions(), &[(0, 0)]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn cells() {
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        assert_eq!(sum.query(&(Cell::new(1), RefCell::new(2))), 3);
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let (cell, ref_cell) = incr.transform((Cell::new(1), RefCell::new(2)));
+        assert_eq!((cell.get(), ref_cell.into_inner()), (2, 3));
+
+        let ref_cell = RefCell::new(1);
+        let mut borrowed = Everything::new(Query::new(|e: &Inaccessible| vec![*e]), |mut a, b| {
+            a.extend(b);
+            a
+        });
+        let guard = ref_cell.borrow_mut();
+        assert_eq!(borrowed.query(&ref_cell), vec![Inaccessible::Borrowed]);
+        drop(guard);
+        assert_eq!(borrowed.query(&ref_cell), vec![]);
+
+        let value = (Cell::new(1), RefCell::new(2));
+        let interior: Vec<_> = value.descendants::<Inaccessible>().collect();
+        assert_eq!(interior, vec![&Inaccessible::Interior, &Inaccessible::Interior]);
+        assert_eq!(value.descendants::<i32>().count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn locks() {
+        use std::panic;
+        use std::thread;
+
+        let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+        let mut value = (Mutex::new(1), RwLock::new(2));
+        assert_eq!(sum.query(&value), 3);
+
+        let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+        zero.mutate(&mut value);
+        assert_eq!(sum.query(&value), 0);
+
+        let mut inaccessible = Everything::new(Query::new(|e: &Inaccessible| vec![*e]), |mut a, b| {
+            a.extend(b);
+            a
+        });
+        {
+            let _guard = value.0.lock().unwrap();
+            assert_eq!(inaccessible.query(&value), vec![Inaccessible::Locked]);
+        }
+        let interior: Vec<_> = value.descendants::<Inaccessible>().collect();
+        assert_eq!(interior, vec![&Inaccessible::Interior, &Inaccessible::Interior]);
+
+        let poisoned = Arc::new(Mutex::new(1));
+        let result = {
+            let poisoned = poisoned.clone();
+            thread::spawn(move || {
+                let _guard = poisoned.lock().unwrap();
+                panic::resume_unwind(Box::new(()));
+            }).join()
+        };
+        assert!(result.is_err());
+        assert_eq!(inaccessible.query(&*poisoned), vec![Inaccessible::Poisoned]);
+
+        let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+        let poisoned = Arc::try_unwrap(poisoned).unwrap();
+        let poisoned = incr.transform(poisoned);
+        assert!(poisoned.is_poisoned());
+        assert_eq!(poisoned.into_inner().unwrap_err().into_inner(), 1);
+    }
+}
+
+// Standard library types that deliberately do not implement `Term`. Wrap any
+// of them in `Leaf` or `Opaque` to embed one in a `Term` anyway.
+//
+// * Iterators, entries, and other views into a collection or string, such as
+//   `vec::Drain`, `btree_map::Entry`, `PeekMut`, `str::Chars`, and
+//   `path::Components`: they are borrowed partway through an operation on
+//   the collection, which traversing them would advance or cut short.
+//   Traverse the collection itself instead.
+//
+// * `cell::Ref` and `cell::RefMut`, and lock guards such as `StdinLock`: they
+//   hold a borrow or a lock, and traversing the cell or lock itself already
+//   reaches its value.
+//
+// * `rc::Weak` and `sync::Weak`: their value may already be dropped, and
+//   upgrading them mid-traversal would share the value, so that it could no
+//   longer be mutated.
+//
+// * `UnsafeCell`: its value can only be reached with `unsafe` code, which
+//   requires guarantees that a traversal cannot make.
+//
+// * The atomic integers, `AtomicBool`, and `AtomicPtr`: every access needs an
+//   `atomic::Ordering`, which a traversal cannot choose.
+//
+// * Handles to resources outside of the program, such as `File`, `TcpStream`,
+//   `Stdin`, `Child`, `Sender`, `Receiver`, `JoinHandle`, `Thread`, and
+//   `LocalKey`, along with builders for them, such as `Command`,
+//   `OpenOptions`, and `thread::Builder`: they are not data to traverse.
+//
+// * `Barrier`, `Condvar`, `Once`, and their results: they synchronize
+//   threads, and hold no data.
+//
+// * Hashers and hasher builders, such as `DefaultHasher`, `RandomState`, and
+//   `BuildHasherDefault`: they are state for hashing values, not values, and
+//   maps and sets do not traverse theirs either.
+//
+// * `io::Error`: it may carry an arbitrary `dyn Error`, which cannot be cast
+//   to a `U`, and treating it as a leaf would silently hide that payload.
 //
-// Below are all the stable `std` types that implement `Debug`, which I figure
-// is either all the stable `std` types, or pretty close to them. We need to
-// implement `Term` for all of these :)
+// * `fmt::Arguments`, `panic::Location`, and `PanicHookInfo`: they only live
+//   for the duration of a formatting or panicking call.
 //
-// enum std::borrow::Cow
-// enum std::cmp::Ordering
-// enum std::collections::Bound
-// enum std::collections::btree_map::Entry
-// enum std::env::VarError
-// enum std::io::CharsError
-// enum std::io::ErrorKind
-// enum std::io::SeekFrom
-// enum std::net::IpAddr
-// enum std::net::Ipv6MulticastScope
-// enum std::net::Shutdown
-// enum std::net::SocketAddr
-// enum std::num::FpCategory
-// enum std::option::Option
-// enum std::os::raw::c_void
-// enum std::path::Component
-// enum std::path::Prefix
-// enum std::result::Result
-// enum std::str::pattern::SearchStep
-// enum std::string::ParseError
-// enum std::sync::TryLockError
-// enum std::sync::atomic::Ordering
-// enum std::sync::mpsc::RecvTimeoutError
-// enum std::sync::mpsc::TryRecvError
-// enum std::sync::mpsc::TrySendError
-// enum std::thread::LocalKeyState
-// struct std::any::TypeId
-// struct std::ascii::EscapeDefault
-// struct std::cell::BorrowError
-// struct std::cell::BorrowMutError
-// struct std::cell::Cell
-// struct std::cell::Ref
-// struct std::cell::RefCell
-// struct std::cell::RefMut
-// struct std::cell::UnsafeCell
-// struct std::char::CharTryFromError
-// struct std::char::DecodeUtf16Error
-// struct std::char::DecodeUtf8
-// struct std::char::EscapeDebug
-// struct std::char::EscapeDefault
-// struct std::char::EscapeUnicode
-// struct std::cmp::Reverse
-// struct std::collections::binary_heap::BinaryHeapPlace
-// struct std::collections::binary_heap::Drain
-// struct std::collections::binary_heap::IntoIter
-// struct std::collections::binary_heap::Iter
-// struct std::collections::binary_heap::PeekMut
-// struct std::collections::btree_map::IntoIter
-// struct std::collections::btree_map::Iter
-// struct std::collections::btree_map::IterMut
-// struct std::collections::btree_map::Keys
-// struct std::collections::btree_map::OccupiedEntry
-// struct std::collections::btree_map::Range
-// struct std::collections::btree_map::RangeMut
-// struct std::collections::btree_map::VacantEntry
-// struct std::collections::btree_map::Values
-// struct std::collections::btree_map::ValuesMut
-// struct std::collections::btree_set::Difference
-// struct std::collections::btree_set::Intersection
-// struct std::collections::btree_set::IntoIter
-// struct std::collections::btree_set::Iter
-// struct std::collections::btree_set::Range
-// struct std::collections::btree_set::SymmetricDifference
-// struct std::collections::btree_set::Union
-// struct std::collections::hash_map::DefaultHasher
-// struct std::collections::hash_map::Drain
-// struct std::collections::hash_map::IterMut
-// struct std::collections::hash_map::RandomState
-// struct std::collections::hash_map::ValuesMut
-// struct std::collections::hash_set::Difference
-// struct std::collections::hash_set::Intersection
-// struct std::collections::hash_set::SymmetricDifference
-// struct std::collections::hash_set::Union
-// struct std::collections::linked_list::BackPlace
-// struct std::collections::linked_list::FrontPlace
-// struct std::collections::linked_list::IntoIter
-// struct std::collections::linked_list::Iter
-// struct std::collections::linked_list::IterMut
-// struct std::collections::vec_deque::Drain
-// struct std::collections::vec_deque::IntoIter
-// struct std::collections::vec_deque::Iter
-// struct std::collections::vec_deque::IterMut
-// struct std::collections::vec_deque::PlaceBack
-// struct std::collections::vec_deque::PlaceFront
-// struct std::env::Args
-// struct std::env::ArgsOs
-// struct std::env::JoinPathsError
-// struct std::env::SplitPaths
-// struct std::env::Vars
-// struct std::env::VarsOs
-// struct std::ffi::CStr
-// struct std::ffi::CString
-// struct std::ffi::FromBytesWithNulError
-// struct std::ffi::IntoStringError
-// struct std::ffi::NulError
-// struct std::ffi::OsStr
-// struct std::ffi::OsString
-// struct std::fmt::Arguments
-// struct std::fmt::Error
-// struct std::fs::DirBuilder
-// struct std::fs::DirEntry
-// struct std::fs::File
-// struct std::fs::FileType
-// struct std::fs::Metadata
-// struct std::fs::OpenOptions
-// struct std::fs::Permissions
-// struct std::fs::ReadDir
-// struct std::hash::BuildHasherDefault
-// struct std::hash::SipHasher
-// struct std::hash::SipHasher13
-// struct std::hash::SipHasher24
-// struct std::io::BufReader
-// struct std::io::Empty
-// struct std::io::Error
-// struct std::io::Repeat
-// struct std::io::Sink
-// struct std::io::Stderr
-// struct std::io::StderrLock
-// struct std::io::Stdin
-// struct std::io::StdinLock
-// struct std::io::Stdout
-// struct std::io::StdoutLock
-// struct std::iter::Chain
-// struct std::iter::Cloned
-// struct std::iter::Cycle
-// struct std::iter::DeprecatedStepBy
-// struct std::iter::Empty
-// struct std::iter::Enumerate
-// struct std::iter::Filter
-// struct std::iter::FilterMap
-// struct std::iter::FlatMap
-// struct std::iter::Fuse
-// struct std::iter::Inspect
-// struct std::iter::Map
-// struct std::iter::Once
-// struct std::iter::Peekable
-// struct std::iter::Repeat
-// struct std::iter::Rev
-// struct std::iter::Scan
-// struct std::iter::Skip
-// struct std::iter::SkipWhile
-// struct std::iter::StepBy
-// struct std::iter::Take
-// struct std::iter::TakeWhile
-// struct std::iter::Zip
-// struct std::marker::PhantomData
-// struct std::mem::Discriminant
-// struct std::net::AddrParseError
-// struct std::net::Incoming
-// struct std::net::Ipv4Addr
-// struct std::net::Ipv6Addr
-// struct std::net::LookupHost
-// struct std::net::SocketAddrV4
-// struct std::net::SocketAddrV6
-// struct std::net::TcpListener
-// struct std::net::TcpStream
-// struct std::net::UdpSocket
-// struct std::num::ParseFloatError
-// struct std::num::ParseIntError
-// struct std::num::TryFromIntError
-// struct std::num::Wrapping
-// struct std::ops::Range
-// struct std::ops::RangeFrom
-// struct std::ops::RangeFull
-// struct std::ops::RangeInclusive
-// struct std::ops::RangeTo
-// struct std::ops::RangeToInclusive
-// struct std::option::IntoIter
-// struct std::option::Iter
-// struct std::option::IterMut
-// struct std::os::unix::net::Incoming
-// struct std::os::unix::net::SocketAddr
-// struct std::os::unix::net::UnixDatagram
-// struct std::os::unix::net::UnixListener
-// struct std::os::unix::net::UnixStream
-// struct std::panic::Location
-// struct std::panic::PanicInfo
-// struct std::path::Components
-// struct std::path::Display
-// struct std::path::Iter
-// struct std::path::Path
-// struct std::path::PathBuf
-// struct std::path::PrefixComponent
-// struct std::path::StripPrefixError
-// struct std::process::Child
-// struct std::process::ChildStderr
-// struct std::process::ChildStdin
-// struct std::process::ChildStdout
-// struct std::process::Command
-// struct std::process::ExitStatus
-// struct std::process::Output
-// struct std::process::Stdio
-// struct std::rc::Rc
-// struct std::rc::Weak
-// struct std::result::IntoIter
-// struct std::result::Iter
-// struct std::result::IterMut
-// struct std::slice::Chunks
-// struct std::slice::ChunksMut
-// struct std::slice::Iter
-// struct std::slice::IterMut
-// struct std::slice::RSplit
-// struct std::slice::RSplitMut
-// struct std::slice::RSplitN
-// struct std::slice::RSplitNMut
-// struct std::slice::Split
-// struct std::slice::SplitMut
-// struct std::slice::SplitN
-// struct std::slice::SplitNMut
-// struct std::slice::Windows
-// struct std::str::Bytes
-// struct std::str::CharIndices
-// struct std::str::Chars
-// struct std::str::EncodeUtf16
-// struct std::str::Lines
-// struct std::str::LinesAny
-// struct std::str::MatchIndices
-// struct std::str::Matches
-// struct std::str::ParseBoolError
-// struct std::str::RMatchIndices
-// struct std::str::RMatches
-// struct std::str::RSplit
-// struct std::str::RSplitN
-// struct std::str::RSplitTerminator
-// struct std::str::Split
-// struct std::str::SplitN
-// struct std::str::SplitTerminator
-// struct std::str::Utf8Error
-// struct std::str::pattern::CharPredicateSearcher
-// struct std::str::pattern::CharSearcher
-// struct std::str::pattern::CharSliceSearcher
-// struct std::str::pattern::StrSearcher
-// struct std::string::Drain
-// struct std::string::FromUtf16Error
-// struct std::string::FromUtf8Error
-// struct std::string::Splice
-// struct std::string::String
-// struct std::sync::Arc
-// struct std::sync::Barrier
-// struct std::sync::BarrierWaitResult
-// struct std::sync::Condvar
-// struct std::sync::Once
-// struct std::sync::OnceState
-// struct std::sync::PoisonError
-// struct std::sync::WaitTimeoutResult
-// struct std::sync::Weak
-// struct std::sync::atomic::AtomicBool
-// struct std::sync::atomic::AtomicI16
-// struct std::sync::atomic::AtomicI32
-// struct std::sync::atomic::AtomicI64
-// struct std::sync::atomic::AtomicI8
-// struct std::sync::atomic::AtomicIsize
-// struct std::sync::atomic::AtomicPtr
-// struct std::sync::atomic::AtomicU16
-// struct std::sync::atomic::AtomicU32
-// struct std::sync::atomic::AtomicU64
-// struct std::sync::atomic::AtomicU8
-// struct std::sync::atomic::AtomicUsize
-// struct std::sync::mpsc::Receiver
-// struct std::sync::mpsc::RecvError
-// struct std::sync::mpsc::Select
-// struct std::sync::mpsc::SendError
-// struct std::sync::mpsc::Sender
-// struct std::sync::mpsc::SyncSender
-// struct std::thread::Builder
-// struct std::thread::JoinHandle
-// struct std::thread::LocalKey
-// struct std::thread::Thread
-// struct std::thread::ThreadId
-// struct std::time::Duration
-// struct std::time::Instant
-// struct std::time::SystemTime
-// struct std::time::SystemTimeError
-// struct std::vec::Drain
-// struct std::vec::IntoIter
-// struct std::vec::PlaceBack
-// struct std::vec::Splice
-// trait std::any::Any
-// trait std::fmt::Debug
-// trait std::io::Write
-// trait std::marker::Send
-// trait std::marker::Sized
-// union std::mem::ManuallyDrop
+// * `c_void`: it has no values.
@@ -1,5 +1,6 @@
-use super::{Cast, GenericQuery, Term};
-use std::marker::PhantomData;
+use super::{Castable, GenericQuery, Term};
+use cast::cast;
+use core::marker::PhantomData;
 
 /// Work around Rust's lack of higher-rank type polymorphism with a trait that
 /// has a generic `fn transform<T>` method. Essentially, we'd really prefer
@@ -35,7 +36,7 @@ where
     pub fn new(f: F) -> Transformation<F, U> {
         Transformation {
             f,
-            phantom: ::std::marker::PhantomData,
+            phantom: PhantomData,
         }
     }
 }
@@ -43,14 +44,18 @@ where
 impl<F, U> GenericTransform for Transformation<F, U>
 where
     F: FnMut(U) -> U,
+    U: Castable,
 {
     #[inline]
-    fn transform<T>(&mut self, t: T) -> T {
-        match Cast::<U>::cast(t) {
-            Ok(u) => match Cast::<T>::cast((self.f)(u)) {
+    fn transform<T>(&mut self, t: T) -> T
+    where
+        T: Term,
+    {
+        match cast::<U, _>(t) {
+            Ok(u) => match cast::<T, _>((self.f)(u)) {
                 Ok(t) => t,
                 Err(_) => unreachable!(
-                    "If T=U, then U=T. Cast isn't pub, so there aren't any \
+                    "If T=U, then U=T. Casting isn't pub, so there aren't any \
                      future specializations that could wreck this for us."
                 ),
             },
@@ -138,6 +143,46 @@ where
     }
 }
 
+/// Transform only the `n`th value given to this transformation, and pass the
+/// rest through untouched. Used to implement `Term::map_nth_transform`.
+pub(crate) struct Nth<'f, F>
+where
+    F: 'f + GenericTransform,
+{
+    n: usize,
+    i: usize,
+    f: &'f mut F,
+}
+
+impl<'f, F> Nth<'f, F>
+where
+    F: 'f + GenericTransform,
+{
+    #[inline]
+    pub(crate) fn new(n: usize, f: &'f mut F) -> Nth<'f, F> {
+        Nth { n, i: 0, f }
+    }
+}
+
+impl<'f, F> GenericTransform for Nth<'f, F>
+where
+    F: 'f + GenericTransform,
+{
+    #[inline]
+    fn transform<T>(&mut self, t: T) -> T
+    where
+        T: Term,
+    {
+        let i = self.i;
+        self.i += 1;
+        if i == self.n {
+            self.f.transform(t)
+        } else {
+            t
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,7 +190,14 @@ mod tests {
     #[test]
     fn transformation() {
         let mut not = Transformation::new(|b: bool| !b);
-        assert_eq!(not.transform(true), false);
+        assert!(!not.transform(true));
         assert_eq!(not.transform("string"), "string");
     }
+
+    #[test]
+    fn transform_nth() {
+        let mut not = Transformation::new(|b: bool| !b);
+        assert_eq!((true, true, true).map_nth_transform(1, &mut not), (true, false, true));
+        assert_eq!([true].map_nth_transform(1, &mut not), [true]);
+    }
 }
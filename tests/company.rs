@@ -63,6 +63,7 @@ impl Default for Company {
 
 impl cmp::Eq for Salary {}
 
+#[allow(clippy::derive_ord_xor_partial_ord)]
 impl cmp::Ord for Salary {
     fn cmp(&self, rhs: &Salary) -> cmp::Ordering {
         assert!(!self.0.is_nan());
@@ -142,6 +143,7 @@ impl IncreaseInPlace for Department {
 }
 
 impl IncreaseInPlace for SubUnit {
+    #[allow(clippy::into_iter_on_ref)]
     fn increase_in_place(&mut self, k: f64) {
         match *self {
             SubUnit::Person(ref mut e) => e.increase_in_place(k),
@@ -190,6 +192,7 @@ impl HighestSalary for Department {
 }
 
 impl HighestSalary for SubUnit {
+    #[allow(clippy::into_iter_on_ref)]
     fn highest_salary(&self) -> Option<Salary> {
         match *self {
             SubUnit::Person(ref e) => e.highest_salary(),
@@ -358,3 +361,239 @@ fn query_highest_salary_scrapping_boilerplate() {
     let company = Company::default();
     assert_eq!(highest_salary.query(&company), Some(Salary(100000.0)));
 }
+
+#[test]
+fn department_payroll_scrapping_boilerplate() {
+    let salaries = Query::new(|s: &Salary| s.0);
+    let mut payrolls = vec![];
+    {
+        let mut payroll = FoldUp::new(
+            salaries,
+            |d: &Department, total: f64| {
+                payrolls.push((d.0, total));
+                total
+            },
+            |a, b| a + b,
+        );
+
+        let company = Company::default();
+        assert_eq!(payroll.query(&company), 111058.5);
+    }
+    assert_eq!(
+        payrolls,
+        vec![("Funsies", 3.0), ("Research", 11058.5), ("Strategy", 100000.0)]
+    );
+}
+
+#[test]
+fn iterate_descendants() {
+    let company = Company::default();
+
+    let names: Vec<_> = company
+        .descendants::<Person>()
+        .filter(|p| p.1 == "Amsterdam")
+        .map(|p| p.0)
+        .collect();
+    assert_eq!(names, vec!["Ralf", "Joost"]);
+
+    let first_two: Vec<_> = company.descendants::<Salary>().take(2).collect();
+    assert_eq!(first_two, vec![&Salary(8000.0), &Salary(33.3)]);
+
+    let departments: Vec<_> = company
+        .descendants::<Department>()
+        .map(|d| d.0)
+        .collect();
+    assert_eq!(departments, vec!["Research", "Funsies", "Strategy"]);
+
+    let employees: Vec<Employee> = company.into_descendants().collect();
+    assert_eq!(employees.len(), 7);
+    assert_eq!(employees[0], Employee(Person("Ralf", "Amsterdam"), Salary(8000.0)));
+}
+
+#[test]
+fn collect_mut_salaries() {
+    let mut company = Company::default();
+    {
+        let mut salaries = company.collect_mut::<Salary>();
+        assert_eq!(salaries.len(), 7);
+        salaries.sort();
+        for s in salaries.iter_mut().take(3) {
+            s.0 = 500.0;
+        }
+    }
+
+    let mut lowest: Vec<_> = company
+        .descendants::<Employee>()
+        .filter(|e| e.1 == Salary(500.0))
+        .map(|e| (e.0).0)
+        .collect();
+    lowest.sort();
+    assert_eq!(lowest, vec!["Jim", "Joe", "Mike"]);
+}
+
+#[test]
+fn query_highest_salary_by_ref() {
+    let query = QueryRef::new(|e: &Employee| Some(&e.1));
+    let mut highest_salary = EverythingRef::new(query, cmp::max);
+
+    let company = Company::default();
+    assert_eq!(highest_salary.query(&company), Some(&Salary(100000.0)));
+}
+
+#[test]
+fn fill_salary_holes() {
+    let company = Company::default();
+    let holes = company.holes::<Salary>();
+    assert_eq!(holes.len(), 7);
+
+    let blair = &holes[6];
+    assert_eq!(blair.get(), &Salary(100000.0));
+    let fairer = blair.fill(Salary(50000.0));
+    assert_eq!(
+        fairer.descendants::<Salary>().cloned().collect::<Vec<_>>(),
+        vec![
+            Salary(8000.0),
+            Salary(33.3),
+            Salary(22.2),
+            Salary(1000.0),
+            Salary(2000.0),
+            Salary(3.0),
+            Salary(50000.0),
+        ]
+    );
+    assert_eq!(company, Company::default());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn rename_owned_names() {
+    #[derive(Clone, Debug, PartialEq, Term)]
+    struct Contact(String, std::path::PathBuf, Option<String>);
+
+    let contacts = vec![
+        Contact("Ralf".into(), "ralf.vcf".into(), None),
+        Contact("Joost".into(), "joost.vcf".into(), Some("Jo".into())),
+    ];
+    let shout = Transformation::new(|s: String| s.to_uppercase());
+    let mut shout_everywhere = Everywhere::new(shout);
+    assert_eq!(
+        shout_everywhere.transform(contacts),
+        vec![
+            Contact("RALF".into(), "ralf.vcf".into(), None),
+            Contact("JOOST".into(), "joost.vcf".into(), Some("JO".into())),
+        ]
+    );
+}
+
+define_visitors! {
+    pub trait Visit, VisitMut, Fold;
+
+    Department => visit_department, visit_department_mut, fold_department;
+    Employee => visit_employee, visit_employee_mut, fold_employee;
+    Salary => visit_salary, visit_salary_mut, fold_salary;
+}
+
+#[derive(Default)]
+struct Payroll(f64);
+
+impl<'ast> Visit<'ast> for Payroll {
+    fn visit_salary(&mut self, salary: &'ast Salary) {
+        self.0 += salary.0;
+    }
+}
+
+#[test]
+fn visit_defaults_recurse_through_unlisted_types() {
+    let company = Company::default();
+    let mut payroll = Payroll::default();
+    payroll.visit_children(&company);
+    assert_eq!(payroll.0, 111058.5);
+}
+
+#[derive(Default)]
+struct Departments<'ast> {
+    names: Vec<&'ast str>,
+    top_level_only: bool,
+}
+
+impl<'ast> Visit<'ast> for Departments<'ast> {
+    fn visit_department(&mut self, department: &'ast Department) {
+        self.names.push(department.0);
+        if !self.top_level_only {
+            self.visit_children(department);
+        }
+    }
+}
+
+#[test]
+fn visit_overrides_control_recursion() {
+    let company = Company::default();
+
+    let mut all = Departments::default();
+    all.visit_children(&company);
+    assert_eq!(all.names, vec!["Research", "Funsies", "Strategy"]);
+
+    let mut top_level = Departments {
+        top_level_only: true,
+        ..Departments::default()
+    };
+    top_level.visit_children(&company);
+    assert_eq!(top_level.names, vec!["Research", "Strategy"]);
+}
+
+struct Raise(f64);
+
+impl VisitMut for Raise {
+    fn visit_salary_mut(&mut self, salary: &mut Salary) {
+        salary.0 *= 1.0 + self.0;
+    }
+}
+
+#[test]
+fn visit_mut_defaults_recurse() {
+    let mut company = Company::default();
+    Raise(0.5).visit_children_mut(&mut company);
+
+    let raised: Vec<_> = company.descendants::<Salary>().map(|s| s.0).collect();
+    let expected: Vec<_> = Company::default()
+        .descendants::<Salary>()
+        .map(|s| s.0 * 1.5)
+        .collect();
+    assert_eq!(raised, expected);
+}
+
+struct Anonymize;
+
+impl Fold for Anonymize {
+    fn fold_employee(&mut self, employee: Employee) -> Employee {
+        let Employee(Person(_, address), salary) = self.fold_children(employee);
+        Employee(Person("Anonymous", address), salary)
+    }
+
+    fn fold_salary(&mut self, _: Salary) -> Salary {
+        Salary(0.0)
+    }
+}
+
+#[test]
+fn fold_rebuilds_listed_types() {
+    let company = Anonymize.fold_children(Company::default());
+
+    let jim = Employee(Person("Anonymous", "Portland"), Salary(0.0));
+    let blair = Employee(Person("Anonymous", "London"), Salary(0.0));
+    assert_eq!(
+        company.0[0].2[3],
+        SubUnit::Department(Box::new(Department("Funsies", jim, vec![])))
+    );
+    assert_eq!(company.0[1], Department("Strategy", blair, vec![]));
+    assert!(company.descendants::<Person>().all(|p| p.0 == "Anonymous"));
+}
+
+struct Identity;
+
+impl Fold for Identity {}
+
+#[test]
+fn fold_defaults_are_the_identity() {
+    assert_eq!(Identity.fold_children(Company::default()), Company::default());
+}
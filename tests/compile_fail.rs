@@ -0,0 +1,9 @@
+extern crate trybuild;
+
+// The expected errors are rustc's, and differ between stable and nightly, so
+// only check them on stable. They also name the default features' impls.
+#[cfg(all(feature = "std", not(feature = "nightly")))]
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "alloc")]
+
+// Renamed, so that `::scrapmetal` does not resolve, and the derive has to use
+// the path given by `#[term(crate = "...")]`.
+extern crate scrapmetal as traversal;
+
+#[macro_use]
+extern crate scrapmetal_derive;
+
+use traversal::{Everything, Everywhere, GenericQuery, GenericTransform, Query, Transformation};
+
+#[derive(Clone, Debug, PartialEq, Term)]
+#[term(crate = "::traversal")]
+pub struct Point {
+    pub x: i32,
+    #[term(opaque)]
+    pub y: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+#[term(crate = "::traversal")]
+pub enum Shape<T> {
+    Line(Point, Point),
+    Polygon { points: Vec<Point>, label: T },
+}
+
+#[test]
+fn crate_path() {
+    let shapes = vec![
+        Shape::Line(Point { x: 1, y: 2 }, Point { x: 3, y: 4 }),
+        Shape::Polygon {
+            points: vec![Point { x: 5, y: 6 }],
+            label: "pentagon".to_string(),
+        },
+    ];
+
+    let mut negate = Everywhere::new(Transformation::new(|i: i32| -i));
+    let shapes = negate.transform(shapes);
+
+    let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&shapes), -9);
+}
@@ -1,3 +1,4 @@
+#![cfg(feature = "alloc")]
 #![allow(dead_code)]
 #![deny(unused_variables)]
 
@@ -6,6 +7,9 @@ extern crate scrapmetal;
 #[macro_use]
 extern crate scrapmetal_derive;
 
+use scrapmetal::*;
+use std::marker::PhantomData;
+
 #[derive(Term)]
 struct UnitStruct;
 
@@ -17,3 +21,584 @@ struct EmptyStruct {}
 
 #[derive(Term)]
 enum EmptyEnum {}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Labeled {
+    pub label: String,
+    pub value: Point,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Polygon {
+    pub name: Option<String>,
+    pub points: Vec<Labeled>,
+    pub closed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Empty {}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub enum Token {
+    Eof,
+    Comma,
+    Ident(String),
+    Number(i64),
+    Call { callee: String, args: Vec<Token> },
+    Nothing(),
+    Nowhere {},
+}
+
+/// Named fields that share their names with the generated code's parameters.
+#[derive(Clone, Debug, PartialEq, Term)]
+pub enum Expr {
+    Int(i32),
+    Call { f: Box<Expr>, each: Vec<Expr> },
+    Let { q: i32, m: Box<Expr>, c: Box<Expr>, r: i32, r#this: i32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub enum Opcode {
+    Nop,
+    Push(u8),
+    Wide(
+        u8, u8, u8, u8, u8, u8, u8, u8, u8, u8,
+        u8, u8, u8, u8, u8, u8, u8, u8, u8, u8,
+        u8, u8, u8, u8, u8, u8, u8, u8, u8, u8,
+    ),
+}
+
+/// Source locations, which do not implement `Term`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span(pub u32, pub u32);
+
+/// An interned name, which does not implement `Term`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Symbol(pub u32);
+
+/// A type from "another crate", which does not implement `Term`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attrs {
+    pub doc: String,
+    pub tags: Vec<String>,
+}
+
+/// Traverses `Attrs` as though it had `doc` and `tags` fields of its own.
+mod attrs {
+    use super::Attrs;
+    use scrapmetal::*;
+
+    pub fn map_one_transform<F>(attrs: Attrs, f: &mut F) -> Attrs
+    where
+        F: GenericTransform,
+    {
+        Attrs {
+            doc: f.transform(attrs.doc),
+            tags: f.transform(attrs.tags),
+        }
+    }
+
+    pub fn map_one_query<Q, R, F>(attrs: &Attrs, q: &mut Q, mut each: F)
+    where
+        Q: GenericQuery<R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = q.query(&attrs.doc);
+        each(q, r);
+        let r = q.query(&attrs.tags);
+        each(q, r);
+    }
+
+    pub fn map_one_query_ref<'a, Q, R, F>(attrs: &'a Attrs, q: &mut Q, mut each: F)
+    where
+        Q: GenericQueryRef<'a, R>,
+        F: FnMut(&mut Q, R),
+    {
+        let r = q.query(&attrs.doc);
+        each(q, r);
+        let r = q.query(&attrs.tags);
+        each(q, r);
+    }
+
+    pub fn map_one_mutation<M, R, F>(attrs: &mut Attrs, m: &mut M, mut each: F)
+    where
+        M: GenericMutate<R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = m.mutate(&mut attrs.doc);
+        each(m, r);
+        let r = m.mutate(&mut attrs.tags);
+        each(m, r);
+    }
+
+    pub fn map_one_mutation_ref<'a, M, R, F>(attrs: &'a mut Attrs, m: &mut M, mut each: F)
+    where
+        M: GenericMutateRef<'a, R>,
+        F: FnMut(&mut M, R),
+    {
+        let r = m.mutate(&mut attrs.doc);
+        each(m, r);
+        let r = m.mutate(&mut attrs.tags);
+        each(m, r);
+    }
+
+    pub fn map_one_consume<C, R, F>(attrs: Attrs, c: &mut C, mut each: F)
+    where
+        C: GenericConsume<R>,
+        F: FnMut(&mut C, R),
+    {
+        let r = c.consume(attrs.doc);
+        each(c, r);
+        let r = c.consume(attrs.tags);
+        each(c, r);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Item {
+    pub name: String,
+    #[term(skip)]
+    pub span: Span,
+    #[term(opaque)]
+    pub symbol: Symbol,
+    #[term(skip)]
+    pub cached_name: String,
+    #[term(with = "attrs")]
+    pub attrs: Attrs,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub enum Node {
+    Item(Item, #[term(skip)] Span),
+    Group {
+        items: Vec<Node>,
+        #[term(opaque)]
+        symbol: Symbol,
+        #[term(with = "attrs")]
+        attrs: Attrs,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Tree<T> {
+    pub value: T,
+    pub children: Vec<Tree<T>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub enum Spanned<T, S> {
+    Node(T, #[term(skip)] S),
+    Missing(#[term(opaque)] S),
+}
+
+/// Type parameters that share their names with the generated methods' own.
+#[derive(Clone, Debug, PartialEq, Term)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Visitors<F, Q, M, C> {
+    pub f: F,
+    pub q: Q,
+    pub m: M,
+    pub c: C,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+#[term(bound = "K: Term + Ord")]
+pub struct Registry<K, V> {
+    pub keys: Vec<K>,
+    pub marker: PhantomData<V>,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Borrowed<'a, T> {
+    pub name: &'a str,
+    pub values: &'a [T],
+}
+
+fn labeled(label: &str, value: Point) -> Labeled {
+    Labeled {
+        label: label.into(),
+        value,
+    }
+}
+
+fn triangle() -> Polygon {
+    Polygon {
+        name: Some("triangle".into()),
+        points: vec![
+            labeled("a", Point { x: 0, y: 0 }),
+            labeled("b", Point { x: 3, y: 0 }),
+            labeled("c", Point { x: 0, y: 4 }),
+        ],
+        closed: true,
+    }
+}
+
+#[test]
+fn transform_named_fields() {
+    let mut flip = Everywhere::new(Transformation::new(|p: Point| Point { x: p.y, y: p.x }));
+    let flipped = flip.transform(triangle());
+    assert_eq!(
+        flipped.points.iter().map(|p| p.value.clone()).collect::<Vec<_>>(),
+        vec![Point { x: 0, y: 0 }, Point { x: 0, y: 3 }, Point { x: 4, y: 0 }]
+    );
+
+    let mut shout = Everywhere::new(Transformation::new(|s: String| s.to_uppercase()));
+    let shouted = shout.transform(triangle());
+    assert_eq!(shouted.name, Some("TRIANGLE".into()));
+    assert_eq!(shouted.points[2].label, "C");
+    assert!(shouted.closed);
+
+    assert_eq!(shout.transform(Empty {}), Empty {});
+}
+
+#[test]
+fn query_named_fields() {
+    let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&triangle()), 7);
+
+    let labels: Vec<_> = triangle()
+        .descendants::<Labeled>()
+        .map(|l| l.label.clone())
+        .collect();
+    assert_eq!(labels, vec!["a", "b", "c"]);
+
+    let x = QueryRef::new(|p: &Point| vec![&p.x]);
+    let mut xs = EverythingRef::new(x, |mut a, b| {
+        a.extend(b);
+        a
+    });
+    let triangle = triangle();
+    assert_eq!(xs.query(&triangle), vec![&0, &3, &0]);
+}
+
+#[test]
+fn mutate_named_fields() {
+    let mut polygon = triangle();
+    let mut scale = MutateEverything::new(Mutation::new(|p: &mut Point| {
+        p.x *= 2;
+        p.y *= 2;
+    }));
+    scale.mutate(&mut polygon);
+    assert_eq!(polygon.points[1].value, Point { x: 6, y: 0 });
+    assert_eq!(polygon.points[2].value, Point { x: 0, y: 8 });
+
+    for closed in polygon.collect_mut::<bool>() {
+        *closed = false;
+    }
+    assert!(!polygon.closed);
+
+    let labels: Vec<_> = polygon.into_descendants::<String>().collect();
+    assert_eq!(labels, vec!["triangle", "a", "b", "c"]);
+}
+
+fn tokens() -> Vec<Token> {
+    vec![
+        Token::Ident("print".into()),
+        Token::Call {
+            callee: "max".into(),
+            args: vec![Token::Number(1), Token::Comma, Token::Ident("x".into())],
+        },
+        Token::Nothing(),
+        Token::Nowhere {},
+        Token::Eof,
+    ]
+}
+
+#[test]
+fn transform_mixed_enum() {
+    let mut commas = Everywhere::new(Transformation::new(|t: Token| match t {
+        Token::Number(_) => Token::Comma,
+        t => t,
+    }));
+    let tokens = commas.transform(tokens());
+    assert_eq!(
+        tokens[1],
+        Token::Call {
+            callee: "max".into(),
+            args: vec![Token::Comma, Token::Comma, Token::Ident("x".into())],
+        }
+    );
+    assert_eq!(tokens[2..], [Token::Nothing(), Token::Nowhere {}, Token::Eof]);
+}
+
+#[test]
+fn query_mixed_enum() {
+    let mut count = Everything::new(Query::new(|_: &Token| 1), |a, b| a + b);
+    assert_eq!(count.query(&tokens()), 8);
+
+    let tokens = tokens();
+    let names: Vec<_> = tokens.descendants::<String>().cloned().collect();
+    assert_eq!(names, vec!["print", "max", "x"]);
+}
+
+#[test]
+fn mutate_mixed_enum() {
+    let mut tokens = tokens();
+    let mut rename = MutateEverything::new(Mutation::new(|t: &mut Token| {
+        if let Token::Ident(ref mut s) = *t {
+            s.push('_');
+        }
+    }));
+    rename.mutate(&mut tokens);
+    let names: Vec<_> = tokens.into_descendants::<String>().collect();
+    assert_eq!(names, vec!["print_", "max", "x_"]);
+}
+
+#[test]
+fn variant_fields_named_like_parameters() {
+    let expr = Expr::Let {
+        q: 1,
+        m: Box::new(Expr::Call {
+            f: Box::new(Expr::Int(2)),
+            each: vec![Expr::Int(3), Expr::Int(4)],
+        }),
+        c: Box::new(Expr::Int(5)),
+        r: 6,
+        r#this: 7,
+    };
+
+    let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&expr), 28);
+
+    let mut double = Everywhere::new(Transformation::new(|i: i32| i * 2));
+    let mut expr = double.transform(expr);
+    assert_eq!(sum.query(&expr), 56);
+
+    let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+    zero.mutate(&mut expr);
+    assert_eq!(sum.query(&expr), 0);
+    assert_eq!(expr.into_descendants::<i32>().count(), 7);
+}
+
+#[test]
+fn wide_tuple_variant() {
+    let wide = Opcode::Wide(
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9,
+        10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+        20, 21, 22, 23, 24, 25, 26, 27, 28, 29,
+    );
+    let mut program = vec![Opcode::Nop, Opcode::Push(7), wide];
+
+    let mut increment = Everywhere::new(Transformation::new(|b: u8| b + 1));
+    program = increment.transform(program);
+
+    let mut sum = Everything::new(Query::new(|b: &u8| u32::from(*b)), |a, b| a + b);
+    assert_eq!(sum.query(&program), 8 + (1..31).sum::<u32>());
+
+    let mut zero = MutateEverything::new(Mutation::new(|b: &mut u8| *b = 0));
+    zero.mutate(&mut program);
+    assert_eq!(sum.query(&program), 0);
+
+    assert_eq!(program[0], Opcode::Nop);
+    assert_eq!(program.into_descendants::<u8>().count(), 31);
+}
+
+fn item(name: &str, symbol: u32) -> Item {
+    Item {
+        name: name.into(),
+        span: Span(symbol, symbol + 1),
+        symbol: Symbol(symbol),
+        cached_name: name.into(),
+        attrs: Attrs {
+            doc: format!("docs for {}", name),
+            tags: vec!["pub".into()],
+        },
+    }
+}
+
+fn module() -> Node {
+    Node::Group {
+        items: vec![
+            Node::Item(item("foo", 1), Span(0, 0)),
+            Node::Item(item("bar", 2), Span(0, 0)),
+        ],
+        symbol: Symbol(3),
+        attrs: Attrs {
+            doc: "the module".into(),
+            tags: vec![],
+        },
+    }
+}
+
+#[test]
+fn skipped_fields() {
+    let mut shout = Everywhere::new(Transformation::new(|s: String| s.to_uppercase()));
+    let foo = shout.transform(item("foo", 1));
+    assert_eq!(foo.name, "FOO");
+    assert_eq!(foo.cached_name, "foo");
+    assert_eq!(foo.span, Span(1, 2));
+
+    let names: Vec<_> = item("foo", 1).into_descendants::<String>().collect();
+    assert_eq!(names, vec!["foo", "docs for foo", "pub"]);
+
+    let mut count = Everything::new(Query::new(|_: &Span| 1), |a, b| a + b);
+    assert_eq!(count.query(&module()), 0);
+}
+
+#[test]
+fn opaque_fields() {
+    let mut scale = Everywhere::new(Transformation::new(|s: Leaf<Symbol>| {
+        Leaf(Symbol(s.0 .0 * 10))
+    }));
+    let module = scale.transform(module());
+    let symbols: Vec<_> = module.descendants::<Leaf<Symbol>>().map(|s| s.0 .0).collect();
+    assert_eq!(symbols, vec![10, 20, 30]);
+
+    let mut module = module;
+    for symbol in module.collect_mut::<Leaf<Symbol>>() {
+        symbol.0 .0 += 1;
+    }
+    let mut sum = Everything::new(Query::new(|s: &Leaf<Symbol>| s.0 .0), |a, b| a + b);
+    assert_eq!(sum.query(&module), 63);
+
+    let mut count = Everything::new(Query::new(|_: &Symbol| 1), |a, b| a + b);
+    assert_eq!(count.query(&module), 0);
+}
+
+#[test]
+fn with_fields() {
+    let mut tag = MutateEverything::new(Mutation::new(|tags: &mut Vec<String>| {
+        tags.push("tagged".into())
+    }));
+    let mut module = module();
+    tag.mutate(&mut module);
+
+    let strings: Vec<_> = module.descendants::<String>().cloned().collect();
+    assert_eq!(
+        strings,
+        vec![
+            "foo", "docs for foo", "pub", "tagged",
+            "bar", "docs for bar", "pub", "tagged",
+            "the module", "tagged",
+        ]
+    );
+
+    let mut shout = Everywhere::new(Transformation::new(|s: String| s.to_uppercase()));
+    let foo = shout.transform(item("foo", 1));
+    assert_eq!(foo.attrs.doc, "DOCS FOR FOO");
+    assert_eq!(foo.attrs.tags, vec!["PUB"]);
+}
+
+fn tree() -> Tree<i32> {
+    Tree {
+        value: 1,
+        children: vec![
+            Tree {
+                value: 2,
+                children: vec![],
+            },
+            Tree {
+                value: 3,
+                children: vec![Tree {
+                    value: 4,
+                    children: vec![],
+                }],
+            },
+        ],
+    }
+}
+
+#[test]
+fn inferred_bounds() {
+    let mut double = Everywhere::new(Transformation::new(|i: i32| i * 2));
+    let values: Vec<_> = double.transform(tree()).into_descendants::<i32>().collect();
+    assert_eq!(values, vec![2, 4, 6, 8]);
+
+    let spanned = vec![Spanned::Node(tree(), Span(0, 4)), Spanned::Missing(Span(4, 5))];
+    let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&spanned), 10);
+    assert_eq!(spanned.descendants::<Leaf<Span>>().count(), 1);
+
+    let registry: Registry<String, Symbol> = Registry {
+        keys: vec!["a".into(), "b".into()],
+        marker: PhantomData,
+    };
+    let mut shout = Everywhere::new(Transformation::new(|s: String| s.to_uppercase()));
+    assert_eq!(shout.transform(registry).keys, vec!["A", "B"]);
+}
+
+#[test]
+fn type_parameters_named_like_generated_ones() {
+    let values: Vec<Either<i32, String>> = vec![Either::Left(1), Either::Right("r".into())];
+    let mut double = Everywhere::new(Transformation::new(|i: i32| i * 2));
+    assert_eq!(
+        double.transform(values),
+        vec![Either::Left(2), Either::Right("r".into())]
+    );
+
+    let mut visitors = Visitors { f: 1, q: 2u8, m: 3, c: "c".to_string() };
+    let mut zero = MutateEverything::new(Mutation::new(|i: &mut i32| *i = 0));
+    zero.mutate(&mut visitors);
+    assert_eq!(visitors, Visitors { f: 0, q: 2, m: 0, c: "c".into() });
+    assert_eq!(visitors.descendants::<String>().count(), 1);
+}
+
+#[test]
+fn lifetime_parameters() {
+    static VALUES: [u32; 3] = [1, 2, 3];
+    let borrowed = Borrowed {
+        name: "static",
+        values: &VALUES,
+    };
+    let mut sum = Everything::new(Query::new(|i: &u32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&borrowed), 6);
+    assert_eq!(borrowed.descendants::<&str>().collect::<Vec<_>>(), vec![&"static"]);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn non_static_lifetime_parameters() {
+    let name = String::from("local");
+    let values = vec![4u32, 5];
+    let borrowed = Borrowed {
+        name: &name,
+        values: &values,
+    };
+    let mut sum = Everything::new(Query::new(|i: &u32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&borrowed), 9);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn non_static_cow_fields() {
+    use std::borrow::Cow;
+
+    #[derive(Clone, Debug, PartialEq, Term)]
+    struct Note<'a> {
+        text: Cow<'a, str>,
+        tags: Cow<'a, [u32]>,
+    }
+
+    let text = String::from("local");
+    let tags = vec![1u32, 2];
+    let note = Note {
+        text: Cow::Borrowed(&text),
+        tags: Cow::Owned(tags.clone()),
+    };
+    let mut sum = Everything::new(Query::new(|i: &u32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&note), 3);
+
+    let mut double = Everywhere::new(Transformation::new(|i: u32| i * 2));
+    let note = double.transform(note);
+    assert_eq!(note.text, "local");
+    assert_eq!(&*note.tags, &[2, 4][..]);
+
+    let mut note = Note {
+        text: Cow::Borrowed(&text),
+        tags: Cow::Borrowed(&tags),
+    };
+    let mut zero = MutateEverything::new(Mutation::new(|i: &mut u32| *i = 0));
+    zero.mutate(&mut note);
+    assert_eq!(&*note.tags, &[1, 2][..]);
+}
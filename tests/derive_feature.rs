@@ -0,0 +1,17 @@
+#![cfg(all(feature = "derive", feature = "alloc"))]
+
+extern crate scrapmetal;
+
+use scrapmetal::{Everywhere, GenericTransform, Term, Transformation};
+
+// Both the trait and the derive come from the one import.
+#[derive(Clone, Debug, PartialEq, Term)]
+pub struct Pair(pub i32, pub String);
+
+#[test]
+fn derive_reexport() {
+    let mut double = Everywhere::new(Transformation::new(|i: i32| i * 2));
+    let pair = double.transform(Pair(21, "answer".into()));
+    assert_eq!(pair, Pair(42, "answer".into()));
+    assert_eq!(pair.descendants::<String>().count(), 1);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "alloc")]
+
+extern crate scrapmetal;
+
+use scrapmetal::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wrapper(pub Vec<i32>, pub &'static str);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pair<T> {
+    pub left: T,
+    pub right: T,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tree<T> {
+    Leaf(T),
+    Node { children: Vec<Tree<T>> },
+    Nil,
+}
+
+impl_term!(struct [] Wrapper { 0 });
+impl_term!(struct [T: Term] Pair<T> { left, right });
+impl_term!(enum [T: Term] Tree<T> {
+    Tree::Leaf { 0: t },
+    Tree::Node { children: children },
+    Tree::Nil {},
+});
+
+fn tree() -> Tree<Pair<Wrapper>> {
+    let pair = |i| Pair {
+        left: Wrapper(vec![i], "left"),
+        right: Wrapper(vec![i, i], "right"),
+    };
+    Tree::Node {
+        children: vec![Tree::Leaf(pair(1)), Tree::Nil, Tree::Leaf(pair(2))],
+    }
+}
+
+#[test]
+fn transform_impl_term() {
+    let mut negate = Everywhere::new(Transformation::new(|i: i32| -i));
+    let mut expected = tree();
+    if let Tree::Node { ref mut children } = expected {
+        for child in children {
+            if let Tree::Leaf(ref mut pair) = *child {
+                for i in pair.left.0.iter_mut().chain(pair.right.0.iter_mut()) {
+                    *i = -*i;
+                }
+            }
+        }
+    }
+    assert_eq!(negate.transform(tree()), expected);
+}
+
+#[test]
+fn query_impl_term() {
+    let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&tree()), 9);
+
+    let mut labels = tree().descendants::<Wrapper>().map(|w| w.1).collect::<Vec<_>>();
+    labels.dedup();
+    assert_eq!(labels, vec!["left", "right", "left", "right"]);
+}
+
+#[test]
+fn mutate_impl_term() {
+    let mut value = tree();
+    let mut clear = MutateEverything::new(Mutation::new(|v: &mut Vec<i32>| v.clear()));
+    clear.mutate(&mut value);
+    let mut sum = Everything::new(Query::new(|i: &i32| *i), |a, b| a + b);
+    assert_eq!(sum.query(&value), 0);
+    assert_eq!(value.collect_mut::<Wrapper>().len(), 4);
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "std")]
+
+extern crate scrapmetal;
+
+#[macro_use]
+extern crate scrapmetal_derive;
+
+use scrapmetal::*;
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::num::*;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Clone, Debug, PartialEq, Term)]
+struct Leaves(
+    u128,
+    i128,
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroUsize,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+    NonZeroIsize,
+    Saturating<u8>,
+    Duration,
+    Instant,
+    SystemTime,
+    Ipv4Addr,
+    Ipv6Addr,
+    IpAddr,
+    SocketAddr,
+    TypeId,
+    PhantomData<String>,
+);
+
+fn leaves() -> Leaves {
+    let now = Instant::now();
+    Leaves(
+        1,
+        -1,
+        NonZeroU8::new(1).unwrap(),
+        NonZeroU16::new(1).unwrap(),
+        NonZeroU32::new(1).unwrap(),
+        NonZeroU64::new(1).unwrap(),
+        NonZeroU128::new(1).unwrap(),
+        NonZeroUsize::new(1).unwrap(),
+        NonZeroI8::new(-1).unwrap(),
+        NonZeroI16::new(-1).unwrap(),
+        NonZeroI32::new(-1).unwrap(),
+        NonZeroI64::new(-1).unwrap(),
+        NonZeroI128::new(-1).unwrap(),
+        NonZeroIsize::new(-1).unwrap(),
+        Saturating(255),
+        Duration::from_secs(1),
+        now,
+        SystemTime::UNIX_EPOCH,
+        Ipv4Addr::LOCALHOST,
+        Ipv6Addr::LOCALHOST,
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 80),
+        TypeId::of::<Leaves>(),
+        PhantomData,
+    )
+}
+
+#[test]
+fn transform_leaves() {
+    let double = Transformation::new(|d: Duration| d * 2);
+    let mut double_everywhere = Everywhere::new(double);
+    let doubled = double_everywhere.transform(leaves());
+    assert_eq!(doubled.15, Duration::from_secs(2));
+
+    let mut fifth = Everywhere::new(Transformation::new(|u: u8| u / 5));
+    assert_eq!(fifth.transform(leaves()).14, Saturating(51));
+}
+
+#[test]
+fn query_leaves() {
+    let ip = Query::new(|ip: &IpAddr| vec![*ip]);
+    let mut ips = Everything::new(ip, |mut a, b| {
+        a.extend(b);
+        a
+    });
+    assert_eq!(ips.query(&leaves()), vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+
+    let mut count = Everything::new(Query::new(|_: &TypeId| 1), |a, b| a + b);
+    assert_eq!(count.query(&leaves()), 1);
+}
+
+#[test]
+fn mutate_leaves() {
+    let mut value = leaves();
+    let mut reset = MutateEverything::new(Mutation::new(|t: &mut SystemTime| {
+        *t = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+    }));
+    reset.mutate(&mut value);
+    assert_eq!(value.17, SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+}
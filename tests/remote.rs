@@ -0,0 +1,228 @@
+#![cfg(feature = "alloc")]
+
+extern crate scrapmetal;
+
+#[macro_use]
+extern crate scrapmetal_derive;
+
+use scrapmetal::*;
+
+/// Stands in for a generated parser crate, whose types do not implement
+/// `Term`.
+mod parser {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Span {
+        pub start: u32,
+        pub end: u32,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Expr {
+        Int(i64),
+        Name(String, Span),
+        Call { callee: String, args: Vec<String> },
+        Hole,
+        Neg(Box<Expr>),
+        Add(Vec<Expr>),
+        Default(Option<Box<Expr>>),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Spanned<T>(pub T, pub Span);
+}
+
+#[derive(Term)]
+#[term(remote = "parser::Span")]
+#[allow(dead_code)]
+struct SpanDef {
+    #[term(opaque)]
+    start: u32,
+    #[term(opaque)]
+    end: u32,
+}
+
+#[derive(Term)]
+#[term(remote = "parser::Expr")]
+#[allow(dead_code)]
+enum ExprDef {
+    Int(i64),
+    Name(String, #[term(with = "SpanDef")] parser::Span),
+    Call { callee: String, args: Vec<String> },
+    Hole,
+    Neg(#[term(mirror = "ExprDef")] Box<parser::Expr>),
+    Add(#[term(mirror = "ExprDef")] Vec<parser::Expr>),
+    Default(#[term(mirror = "ExprDef")] Option<Box<parser::Expr>>),
+}
+
+type Expr = Remote<parser::Expr, ExprDef>;
+
+#[derive(Term)]
+#[term(remote = "parser::Spanned")]
+#[allow(dead_code)]
+struct SpannedDef<T>(T, #[term(skip)] parser::Span);
+
+#[derive(Clone, Debug, PartialEq, Term)]
+struct Module {
+    name: String,
+    #[term(mirror = "ExprDef")]
+    body: parser::Expr,
+    #[term(with = "SpannedDef")]
+    exports: parser::Spanned<Vec<String>>,
+}
+
+fn span(start: u32, end: u32) -> parser::Span {
+    parser::Span { start, end }
+}
+
+fn module(body: parser::Expr) -> Module {
+    Module {
+        name: "main".into(),
+        body,
+        exports: parser::Spanned(vec!["run".into()], span(0, 3)),
+    }
+}
+
+#[test]
+fn transform_remote() {
+    let mut shout = Everywhere::new(Transformation::new(|s: String| s.to_uppercase()));
+    let shouted = shout.transform(module(parser::Expr::Call {
+        callee: "print".into(),
+        args: vec!["x".into(), "y".into()],
+    }));
+    assert_eq!(shouted.name, "MAIN");
+    assert_eq!(
+        shouted.body,
+        parser::Expr::Call {
+            callee: "PRINT".into(),
+            args: vec!["X".into(), "Y".into()],
+        }
+    );
+    assert_eq!(shouted.exports, parser::Spanned(vec!["RUN".into()], span(0, 3)));
+
+    let mut negate = Everywhere::new(Transformation::new(|i: i64| -i));
+    assert_eq!(negate.transform(module(parser::Expr::Int(4))).body, parser::Expr::Int(-4));
+
+    let hole = module(parser::Expr::Hole);
+    assert_eq!(shout.transform(hole).body, parser::Expr::Hole);
+}
+
+#[test]
+fn query_remote() {
+    let name = module(parser::Expr::Name("x".into(), span(4, 5)));
+    let strings: Vec<_> = name.descendants::<String>().cloned().collect();
+    assert_eq!(strings, vec!["main", "x", "run"]);
+
+    let mut ends = Everything::new(Query::new(|i: &Leaf<u32>| i.0), |a, b| a + b);
+    assert_eq!(ends.query(&name), 9);
+}
+
+#[test]
+fn mutate_remote() {
+    let mut call = module(parser::Expr::Call {
+        callee: "f".into(),
+        args: vec![],
+    });
+    let mut add_arg = MutateEverything::new(Mutation::new(|args: &mut Vec<String>| {
+        args.push("arg".into())
+    }));
+    add_arg.mutate(&mut call);
+    assert_eq!(
+        call.body,
+        parser::Expr::Call {
+            callee: "f".into(),
+            args: vec!["arg".into()],
+        }
+    );
+    assert_eq!(call.exports.0, vec!["run", "arg"]);
+
+    for s in call.collect_mut::<String>() {
+        s.push('!');
+    }
+    let strings: Vec<_> = call.into_descendants::<String>().collect();
+    assert_eq!(strings, vec!["main!", "f!", "arg!", "run!", "arg!"]);
+}
+
+fn sum(terms: Vec<parser::Expr>) -> parser::Expr {
+    parser::Expr::Add(terms)
+}
+
+fn neg(expr: parser::Expr) -> parser::Expr {
+    parser::Expr::Neg(Box::new(expr))
+}
+
+fn int(i: i64) -> parser::Expr {
+    parser::Expr::Int(i)
+}
+
+#[test]
+fn transform_recursive_remote() {
+    let mut fold = Everywhere::new(Transformation::new(|e: Expr| match e.0 {
+        parser::Expr::Neg(inner) => match *inner {
+            parser::Expr::Int(i) => Remote::new(int(-i)),
+            inner => Remote::new(neg(inner)),
+        },
+        parser::Expr::Add(terms) => {
+            let ints: Option<Vec<i64>> = terms.iter()
+                .map(|t| match *t {
+                    parser::Expr::Int(i) => Some(i),
+                    _ => None,
+                })
+                .collect();
+            match ints {
+                Some(ints) => Remote::new(int(ints.into_iter().sum())),
+                None => Remote::new(sum(terms)),
+            }
+        }
+        e => Remote::new(e),
+    }));
+
+    let expr = sum(vec![int(1), neg(int(2)), neg(sum(vec![int(3), int(4)]))]);
+    assert_eq!(fold.transform(Expr::new(expr.clone())).0, int(-8));
+    assert_eq!(fold.transform(module(expr)).body, int(-8));
+
+    let hole = sum(vec![int(1), parser::Expr::Hole]);
+    assert_eq!(fold.transform(Expr::new(hole.clone())).0, hole);
+}
+
+#[test]
+fn query_recursive_remote() {
+    let body = parser::Expr::Default(Some(Box::new(sum(vec![int(1), neg(int(2))]))));
+    let mut count = Everything::new(Query::new(|_: &Expr| 1), |a, b| a + b);
+    assert_eq!(count.query(&module(body.clone())), 5);
+
+    let mut ints = Everything::new(Query::new(|i: &i64| vec![*i]), |mut a, b| {
+        a.extend(b);
+        a
+    });
+    assert_eq!(ints.query(&Expr::new(body.clone())), vec![1, 2]);
+
+    let body = Expr::new(body);
+    let negated: Vec<_> = body.descendants::<Expr>()
+        .filter_map(|e| match **e {
+            parser::Expr::Neg(ref inner) => Some(&**inner),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(negated, vec![&int(2)]);
+}
+
+#[test]
+fn mutate_recursive_remote() {
+    let mut expr = Expr::new(neg(sum(vec![int(1), parser::Expr::Default(None)])));
+    let mut fill = MutateEverything::new(Mutation::new(|e: &mut Expr| {
+        if let parser::Expr::Default(ref mut default) = e.0 {
+            default.get_or_insert_with(|| Box::new(int(0)));
+        }
+    }));
+    fill.mutate(&mut expr);
+    assert_eq!(
+        expr.0,
+        neg(sum(vec![int(1), parser::Expr::Default(Some(Box::new(int(0))))]))
+    );
+
+    let strings = Expr::new(parser::Expr::Call {
+        callee: "f".into(),
+        args: vec!["x".into()],
+    });
+    assert_eq!(strings.into_descendants::<String>().collect::<Vec<_>>(), vec!["f", "x"]);
+}
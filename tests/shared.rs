@@ -0,0 +1,128 @@
+#![cfg(feature = "std")]
+
+extern crate scrapmetal;
+
+#[macro_use]
+extern crate scrapmetal_derive;
+
+use scrapmetal::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, PartialEq, Term)]
+struct Node {
+    value: i32,
+    children: Vec<Rc<RefCell<Node>>>,
+}
+
+#[derive(Debug, PartialEq, Term)]
+struct State {
+    count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Term)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn leaf(value: i32) -> Rc<RefCell<Node>> {
+    Rc::new(RefCell::new(Node {
+        value,
+        children: vec![],
+    }))
+}
+
+#[test]
+fn mutate_through_shared_ref_cells() {
+    let shared = leaf(1);
+    let mut root = Node {
+        value: 0,
+        children: vec![shared.clone(), shared.clone()],
+    };
+
+    let mut incr = MutateEverything::new(Mutation::new(|i: &mut i32| *i += 1));
+    incr.mutate(&mut root);
+
+    // The shared node is reached through both of the root's handles.
+    assert_eq!(root.value, 1);
+    assert_eq!(shared.borrow().value, 3);
+    assert!(root.children.iter().all(|child| Rc::ptr_eq(child, &shared)));
+    assert!(root.children.iter().all(|child| child.borrow().value == 3));
+}
+
+#[test]
+fn shared_ref_cell_borrowed_during_mutation() {
+    let shared = leaf(1);
+    let mut root = Node {
+        value: 0,
+        children: vec![shared.clone()],
+    };
+
+    let _guard = shared.borrow();
+    let mut borrowed = MutateEverything::with_query(
+        Mutation::new(|e: &mut Inaccessible| vec![*e]),
+        |mut a, b| {
+            a.extend(b);
+            a
+        },
+    );
+    assert_eq!(borrowed.mutate(&mut root), vec![Inaccessible::Borrowed]);
+}
+
+#[test]
+fn query_and_mutate_through_shared_mutexes() {
+    let state = Arc::new(Mutex::new(State { count: 1 }));
+    let mut handles = (state.clone(), vec![state.clone()]);
+
+    let mut sum = Everything::new(Query::new(|c: &u32| *c), |a, b| a + b);
+    assert_eq!(sum.query(&handles), 2);
+
+    let mut incr = MutateEverything::new(Mutation::new(|c: &mut u32| *c += 1));
+    incr.mutate(&mut handles);
+    assert_eq!(state.lock().unwrap().count, 3);
+    assert_eq!(handles.0.lock().unwrap().count, 3);
+    assert_eq!(handles.1[0].lock().unwrap().count, 3);
+
+    let mut locked = MutateEverything::with_query(
+        Mutation::new(|e: &mut Inaccessible| vec![*e]),
+        |mut a, b| {
+            a.extend(b);
+            a
+        },
+    );
+    let _guard = state.lock().unwrap();
+    assert_eq!(
+        locked.mutate(&mut handles),
+        vec![Inaccessible::Locked, Inaccessible::Locked]
+    );
+}
+
+#[test]
+fn transform_shared_pointers() {
+    let mut incr = Everywhere::new(Transformation::new(|i: i32| i + 1));
+
+    // A `Clone` value is cloned-on-write.
+    let point = Rc::new(Point { x: 1, y: 2 });
+    let cloned = incr.transform(point.clone());
+    assert_eq!((&*point, &*cloned), (&Point { x: 1, y: 2 }, &Point { x: 2, y: 3 }));
+
+    // A cell is never cloned, so a shared one is left untouched.
+    let shared = leaf(1);
+    let mut seen = vec![];
+    let same = {
+        let mut record = Everywhere::new(Transformation::new(|e: Inaccessible| {
+            seen.push(e);
+            e
+        }));
+        record.transform(shared.clone())
+    };
+    assert!(Rc::ptr_eq(&same, &shared));
+    assert_eq!(seen, vec![Inaccessible::Shared]);
+
+    // An unshared one is moved out and transformed.
+    drop(same);
+    let transformed = incr.transform(shared);
+    assert_eq!(transformed.borrow().value, 2);
+}
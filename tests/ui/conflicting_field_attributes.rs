@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate scrapmetal_derive;
+extern crate scrapmetal;
+
+#[derive(Term)]
+pub struct Window {
+    #[term(skip, opaque)]
+    pub title: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate scrapmetal_derive;
+extern crate scrapmetal;
+
+#[derive(Term)]
+#[term(bound = "T Term")]
+pub struct Wrapper<T>(pub T);
+
+fn main() {}
@@ -0,0 +1,19 @@
+#[macro_use]
+extern crate scrapmetal_derive;
+extern crate scrapmetal;
+
+pub struct Handle(u32);
+
+#[derive(Term)]
+pub struct Window {
+    pub title: String,
+    pub handle: Handle,
+}
+
+#[derive(Term)]
+pub enum Event {
+    Resize(Window),
+    Close { handle: Handle },
+}
+
+fn main() {}
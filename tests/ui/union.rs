@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate scrapmetal_derive;
+extern crate scrapmetal;
+
+#[derive(Term)]
+pub union Bits {
+    pub int: u32,
+    pub float: f32,
+}
+
+fn main() {}
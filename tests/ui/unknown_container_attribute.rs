@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate scrapmetal_derive;
+extern crate scrapmetal;
+
+#[derive(Term)]
+#[term(rename = "Other")]
+pub struct Wrapper(pub u32);
+
+fn main() {}
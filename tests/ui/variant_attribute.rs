@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate scrapmetal_derive;
+extern crate scrapmetal;
+
+#[derive(Term)]
+pub enum Shape {
+    #[term(skip)]
+    Circle(u32),
+    Square(u32),
+}
+
+fn main() {}